@@ -0,0 +1,162 @@
+// ai.rs
+
+//! Controlador de las motos enemigas: patrullan, persiguen al jugador cuando lo ven
+//! y, en modo moto de luz, dejan estela. Usan las mismas reglas de `is_wall` que el jugador,
+//! así que tampoco atraviesan estelas (el pathfinding ya las trata como paredes).
+
+use raylib::prelude::Vector2;
+
+use crate::caster::line_of_sight;
+use crate::doors::{is_door, Doors};
+use crate::maze::{cell_at, circle_hits_wall, is_wall, world_to_cell, Maze};
+use crate::pathfind::{distances_from, find_path};
+use crate::player::Player;
+use crate::trail::TrailWriter;
+
+/// Qué está haciendo la moto.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiMode {
+    Patrol,
+    Chase,
+}
+
+/// Cuadros entre recálculos del camino.
+const REPATH_FRAMES: u32 = 15;
+
+#[derive(Clone, Debug)]
+pub struct AiController {
+    pub mode: AiMode,
+    pub speed: f32,  // unidades de mundo por cuadro
+    pub radius: f32, // cuerpo para colisiones
+    pub sight: f32,  // distancia a la que empieza a perseguir
+    pub heading: f32,
+    pub crashed: bool, // quedó dentro de una pared (una estela que otra moto dejó encima)
+    route: Vec<(usize, usize)>, // puntos de patrulla (celdas)
+    waypoint: usize,
+    path: Vec<(usize, usize)>,
+    repath_in: u32,
+    trail: Option<TrailWriter>,
+}
+
+impl AiController {
+    /// Patrulla de ida y vuelta entre `start` y la celda más lejana que alcanza desde ahí.
+    pub fn patrol_from(maze: &Maze, start: (usize, usize), speed: f32) -> Self {
+        Self {
+            mode: AiMode::Patrol,
+            speed,
+            radius: 24.0,
+            sight: 900.0,
+            heading: 0.0,
+            crashed: false,
+            route: patrol_route(maze, start),
+            waypoint: 1,
+            path: Vec::new(),
+            repath_in: 0,
+            trail: None,
+        }
+    }
+
+    /// Activa la estela de moto de luz.
+    pub fn with_trail(mut self) -> Self {
+        self.trail = Some(TrailWriter::new());
+        self
+    }
+
+    /// Un cuadro de IA: elige objetivo, sigue el camino y mueve `pos`.
+    /// Devuelve `true` si alcanzó al jugador. Si su celda se volvió pared queda `crashed`
+    /// y ya no hace nada.
+    pub fn update(
+        &mut self,
+        pos: &mut Vector2,
+        maze: &mut Maze,
+        doors: &Doors,
+        player: &Player,
+        block_size: usize,
+    ) -> bool {
+        let here = world_to_cell(pos.x, pos.y, block_size);
+        let in_wall = is_wall(maze, here.0, here.1) && !cell_at(maze, here.0, here.1).is_some_and(is_door);
+        if self.crashed || in_wall {
+            self.crashed = true;
+            return false;
+        }
+        let to_player = ((player.pos.x - pos.x).powi(2) + (player.pos.y - pos.y).powi(2)).sqrt();
+
+        // Persigue mientras lo vea; si lo pierde vuelve a patrullar
+        let sees = to_player < self.sight && line_of_sight(maze, doors, *pos, player.pos, block_size);
+        let mode = if sees { AiMode::Chase } else { AiMode::Patrol };
+        if mode != self.mode {
+            self.mode = mode;
+            self.repath_in = 0;
+        }
+
+        if self.mode == AiMode::Patrol && here == self.route[self.waypoint] {
+            self.waypoint = (self.waypoint + 1) % self.route.len();
+            self.repath_in = 0;
+        }
+        let target = match self.mode {
+            AiMode::Chase => world_to_cell(player.pos.x, player.pos.y, block_size),
+            AiMode::Patrol => self.route[self.waypoint],
+        };
+
+        if self.repath_in == 0 || self.path.is_empty() {
+            self.path = find_path(maze, here, target).unwrap_or_default();
+            if self.path.is_empty() && self.mode == AiMode::Patrol {
+                // una estela cortó la ruta: patrulla hacia lo que todavía se alcanza
+                self.route = patrol_route(maze, here);
+                self.waypoint = 1;
+                self.path = find_path(maze, here, self.route[1]).unwrap_or_default();
+            }
+            self.repath_in = REPATH_FRAMES;
+        } else {
+            self.repath_in -= 1;
+        }
+        // descarta lo ya recorrido
+        if let Some(k) = self.path.iter().position(|&c| c == here) {
+            self.path.drain(..k);
+        }
+
+        // Apunta al centro de la siguiente celda (o al jugador si ya está en su celda)
+        let bs = block_size as f32;
+        let aim = match self.path.get(1) {
+            Some(&(i, j)) => Vector2::new((j as f32 + 0.5) * bs, (i as f32 + 0.5) * bs),
+            None if self.mode == AiMode::Chase => player.pos,
+            None => Vector2::new((here.1 as f32 + 0.5) * bs, (here.0 as f32 + 0.5) * bs),
+        };
+        let (dx, dy) = (aim.x - pos.x, aim.y - pos.y);
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist > 0.5 {
+            self.heading = dy.atan2(dx);
+            let step = self.speed.min(dist);
+            let next = Vector2::new(pos.x + dx / dist * step, pos.y + dy / dist * step);
+
+            // Colisión por eje, igual que el jugador
+            let mut blocked = false;
+            if !circle_hits_wall(maze, Vector2::new(next.x, pos.y), self.radius, block_size) {
+                pos.x = next.x;
+            } else {
+                blocked = true;
+            }
+            if !circle_hits_wall(maze, Vector2::new(pos.x, next.y), self.radius, block_size) {
+                pos.y = next.y;
+            } else {
+                blocked = true;
+            }
+            if blocked {
+                self.path.clear(); // recalcula el siguiente cuadro
+            }
+        }
+
+        if let Some(trail) = self.trail.as_mut() {
+            let others = [(player.pos, player.radius + player.wall_buffer)];
+            trail.update(maze, *pos, self.radius, &others, block_size);
+        }
+
+        to_player < self.radius + player.radius
+    }
+}
+
+/// Ida y vuelta entre `start` y la celda más lejana que se alcanza desde ahí.
+fn patrol_route(maze: &Maze, start: (usize, usize)) -> Vec<(usize, usize)> {
+    let far = distances_from(maze, start).last().map_or(start, |&(cell, _)| cell);
+    vec![start, far]
+}
@@ -1,51 +1,311 @@
 // caster.rs
 
-use raylib::color::Color;
+use raylib::prelude::Vector2;
 
-use crate::framebuffer::Framebuffer;
-use crate::maze::Maze;
-use crate::player::Player;
+use crate::doors::{is_closed_door, Door, Doors, OPEN_DOOR_CELL};
+use crate::maze::{Maze, cell_at, is_marker, is_transparent, maze_width};
+
+/// Cara de la celda que golpeó el rayo.
+/// `North` es el borde superior de la celda (y menor) y `West` el izquierdo (x menor).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WallFace {
+  North,
+  South,
+  East,
+  West,
+}
+
+/// Resultado de un rayo: chocó con pared, llegó a la distancia máxima
+/// o salió del mapa (borde abierto). `Open` (celda vacía) sólo aparece en `walk_ray`;
+/// `Transparent` es una celda que se ve pero no detiene el rayo (ver `TRANSPARENT_CELLS`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitKind {
+  Wall,
+  NoHit,
+  OutOfBounds,
+  Open,
+  Transparent,
+}
 
 pub struct Intersect {
+  pub kind: HitKind,
   pub distance: f32,
-  pub impact: char
+  pub impact: char,
+  pub hit: Vector2,       // punto de impacto en coordenadas de mundo
+  pub face: WallFace,     // cara golpeada
+  pub cell: (usize, usize), // (fila, columna) de la celda golpeada
+  pub slide: f32,         // cuánto se corrió la textura (hoja de una puerta)
+}
+
+impl Intersect {
+  /// `true` si el rayo terminó en una pared.
+  pub fn is_hit(&self) -> bool {
+    self.kind == HitKind::Wall
+  }
+
+  /// Coordenada horizontal de textura (0..1) a lo largo de la cara golpeada.
+  /// Se invierte en East/North para que las texturas no salgan en espejo.
+  /// En una puerta la textura se mueve con la hoja.
+  pub fn texture_u(&self, block_size: usize) -> f32 {
+    let bs = block_size as f32;
+    let fx = (self.hit.x / bs).rem_euclid(1.0) - self.slide;
+    let fy = (self.hit.y / bs).rem_euclid(1.0) - self.slide;
+    match self.face {
+      WallFace::West => fy,
+      WallFace::East => 1.0 - fy,
+      WallFace::South => fx,
+      WallFace::North => 1.0 - fx,
+    }
+  }
+}
+
+/// Rayo junto con las celdas que atravesó, en orden (incluye la de origen
+/// y la golpeada si están dentro del mapa), y las celdas transparentes que cruzó.
+pub struct RayTrace {
+  pub hit: Intersect,
+  pub cells: Vec<(usize, usize)>,
+  pub through: Vec<Intersect>,
 }
 
+/// Lanza un rayo desde `origin` con ángulo `a` y devuelve el primer impacto opaco.
+/// Las celdas transparentes no lo detienen. No dibuja nada: sirve para el render,
+/// la IA, herramientas y pruebas. Se detiene en `max_distance` (unidades de mundo)
+/// o al salir del mapa; las celdas que faltan en filas cortas cuentan como vacías.
 pub fn cast_ray(
-  framebuffer: &mut Framebuffer,
   maze: &Maze,
-  player: &Player,
+  doors: &Doors,
+  origin: Vector2,
   a: f32,
   block_size: usize,
-  draw_line: bool,
+  max_distance: f32,
 ) -> Intersect {
-  let mut d = 0.0;
+  walk_ray(maze, doors, origin, a, block_size, max_distance)
+    .find(|step| !matches!(step.kind, HitKind::Open | HitKind::Transparent))
+    .expect("RayWalk siempre termina con NoHit u OutOfBounds")
+}
 
-  framebuffer.set_current_color(Color::WHITESMOKE);
+/// Igual que `cast_ray`, pero también registra las celdas visitadas
+/// y los impactos con celdas transparentes (de cerca a lejos).
+pub fn trace_ray(
+  maze: &Maze,
+  doors: &Doors,
+  origin: Vector2,
+  a: f32,
+  block_size: usize,
+  max_distance: f32,
+) -> RayTrace {
+  let walk = walk_ray(maze, doors, origin, a, block_size, max_distance);
+  let mut cells: Vec<(usize, usize)> = walk.start_cell().into_iter().collect();
+  let mut through = Vec::new();
+  for step in walk {
+    match step.kind {
+      HitKind::Open => cells.push(step.cell),
+      HitKind::Transparent => {
+        cells.push(step.cell);
+        through.push(step);
+      }
+      HitKind::Wall => {
+        cells.push(step.cell);
+        return RayTrace { hit: step, cells, through };
+      }
+      HitKind::NoHit | HitKind::OutOfBounds => return RayTrace { hit: step, cells, through },
+    }
+  }
+  unreachable!("RayWalk siempre termina con NoHit u OutOfBounds")
+}
 
-  loop {
-    let cos = d * a.cos();
-    let sin = d * a.sin();
-    let x = (player.pos.x + cos) as usize;
-    let y = (player.pos.y + sin) as usize;
+/// `true` si no hay pared entre `from` y `to` (línea de visión).
+pub fn line_of_sight(maze: &Maze, doors: &Doors, from: Vector2, to: Vector2, block_size: usize) -> bool {
+  let dx = to.x - from.x;
+  let dy = to.y - from.y;
+  let dist = (dx * dx + dy * dy).sqrt();
+  if dist < 0.0001 {
+    return true;
+  }
+  let hit = cast_ray(maze, doors, from, dy.atan2(dx), block_size, dist);
+  !hit.is_hit()
+}
 
-    let i = x / block_size;
-    let j = y / block_size;
+/// Recorre el rayo sin detenerse en las paredes: entrega un `Intersect` por cada celda
+/// que cruza (`Open`, `Transparent` o `Wall`) y termina con `NoHit` u `OutOfBounds`.
+/// Sirve para dibujar paredes bajas con otras más altas detrás.
+/// Una puerta es `Wall` sólo si el rayo toca su hoja (según `doors`); si pasa por el hueco es `Open`.
+/// Si el rayo sale de dentro de una puerta, el primer paso puede ser su propia hoja.
+pub fn walk_ray<'a>(
+  maze: &'a Maze,
+  doors: &'a Doors,
+  origin: Vector2,
+  a: f32,
+  block_size: usize,
+  max_distance: f32,
+) -> RayWalk<'a> {
+  let bs = block_size as f32;
+  let dir = Vector2::new(a.cos(), a.sin());
 
-    if maze[j][i] != ' ' {
-      return Intersect{
-        distance: d,
-        impact: maze[j][i]
-      };
-    }
+  // Posición en unidades de celda
+  let px = origin.x / bs;
+  let py = origin.y / bs;
+  let map_x = px.floor() as i32;
+  let map_y = py.floor() as i32;
 
-    if draw_line {
-      framebuffer.set_pixel(x as u32, y as u32);
-    }
+  // Distancia (en celdas) que avanza el rayo para cruzar una celda en x / en y
+  let delta_x = if dir.x == 0.0 { f32::INFINITY } else { (1.0 / dir.x).abs() };
+  let delta_y = if dir.y == 0.0 { f32::INFINITY } else { (1.0 / dir.y).abs() };
 
-    d += 10.0;
+  let (step_x, side_x) = if dir.x < 0.0 {
+    (-1, (px - map_x as f32) * delta_x)
+  } else {
+    (1, (map_x as f32 + 1.0 - px) * delta_x)
+  };
+  let (step_y, side_y) = if dir.y < 0.0 {
+    (-1, (py - map_y as f32) * delta_y)
+  } else {
+    (1, (map_y as f32 + 1.0 - py) * delta_y)
+  };
+
+  RayWalk {
+    maze,
+    doors,
+    origin,
+    dir,
+    bs,
+    max_distance,
+    cols: maze_width(maze) as i32,
+    map: (map_x, map_y),
+    step: (step_x, step_y),
+    side: (side_x, side_y),
+    delta: (delta_x, delta_y),
+    at_start: true,
+    done: false,
   }
 }
 
+/// Recorrido DDA en curso: avanza celda por celda, saltando de borde en borde.
+pub struct RayWalk<'a> {
+  maze: &'a Maze,
+  doors: &'a Doors,
+  origin: Vector2,
+  dir: Vector2,
+  bs: f32,
+  max_distance: f32,
+  cols: i32,
+  map: (i32, i32),   // (x, y) de la celda actual
+  step: (i32, i32),
+  side: (f32, f32),  // distancia (en celdas) al próximo borde en x / en y
+  delta: (f32, f32),
+  at_start: bool,    // falta revisar la celda de salida (puede tener una puerta cerrándose)
+  done: bool,
+}
 
+impl RayWalk<'_> {
+  fn in_bounds(&self, x: i32, y: i32) -> bool {
+    x >= 0 && y >= 0 && y < self.maze.len() as i32 && x < self.cols
+  }
+
+  /// Choque con la hoja de la puerta de la celda (i, j), que el rayo cruza entre
+  /// las distancias `enter` y `exit`: (distancia, cara, corrimiento de la hoja).
+  /// `None` si pasa por la parte abierta.
+  fn door_hit(&self, door: &Door, (i, j): (usize, usize), enter: f32, exit: f32) -> Option<(f32, WallFace, f32)> {
+    let (o, d, plane, along_o, along_d, first) = if door.along_x {
+      let face = if self.dir.y > 0.0 { WallFace::North } else { WallFace::South };
+      (self.origin.y, self.dir.y, (i as f32 + 0.5) * self.bs, self.origin.x, self.dir.x, (face, j))
+    } else {
+      let face = if self.dir.x > 0.0 { WallFace::West } else { WallFace::East };
+      (self.origin.x, self.dir.x, (j as f32 + 0.5) * self.bs, self.origin.y, self.dir.y, (face, i))
+    };
+    if d == 0.0 { return None; }
+    let t = (plane - o) / d;
+    if t < enter || t > exit || t > self.max_distance { return None; }
+    let along = (along_o + along_d * t) / self.bs - first.1 as f32;
+    (along >= door.open).then_some((t, first.0, door.open))
+  }
+
+  /// Celda (fila, columna) donde empieza el rayo, si está dentro del mapa.
+  pub fn start_cell(&self) -> Option<(usize, usize)> {
+    let (x, y) = self.map;
+    self.in_bounds(x, y).then_some((y as usize, x as usize))
+  }
+}
 
+impl Iterator for RayWalk<'_> {
+  type Item = Intersect;
+
+  fn next(&mut self) -> Option<Intersect> {
+    if self.done {
+      return None;
+    }
+
+    // Parado dentro de una puerta: su hoja puede estar entre el origen y el primer borde
+    if self.at_start {
+      self.at_start = false;
+      if let Some(cell) = self.start_cell()
+        && let Some(c) = cell_at(self.maze, cell.0, cell.1)
+        && is_closed_door(c)
+        && let Some(door) = self.doors.get(cell)
+        && let Some((t, face, slide)) = self.door_hit(door, cell, 0.0, self.side.0.min(self.side.1) * self.bs)
+      {
+        let hit = Vector2::new(self.origin.x + self.dir.x * t, self.origin.y + self.dir.y * t);
+        return Some(Intersect { kind: HitKind::Wall, distance: t, impact: c, hit, face, cell, slide });
+      }
+    }
+
+    // Avanza al siguiente borde más cercano
+    let (d, face) = if self.side.0 < self.side.1 {
+      self.map.0 += self.step.0;
+      let d = self.side.0;
+      self.side.0 += self.delta.0;
+      (d, if self.step.0 > 0 { WallFace::West } else { WallFace::East })
+    } else {
+      self.map.1 += self.step.1;
+      let d = self.side.1;
+      self.side.1 += self.delta.1;
+      (d, if self.step.1 > 0 { WallFace::North } else { WallFace::South })
+    };
+
+    let (map_x, map_y) = self.map;
+    let mut distance = d * self.bs;
+    let mut face = face;
+    let mut slide = 0.0;
+    let kind = if distance > self.max_distance {
+      HitKind::NoHit
+    } else if !self.in_bounds(map_x, map_y) {
+      HitKind::OutOfBounds
+    } else {
+      let cell = (map_y as usize, map_x as usize);
+      match cell_at(self.maze, cell.0, cell.1) {
+        Some(c) if is_closed_door(c) => {
+          // la hoja está en medio de la celda: se choca (o no) antes de salir de ella
+          let exit = self.side.0.min(self.side.1) * self.bs;
+          match self.doors.get(cell).map(|door| self.door_hit(door, cell, distance, exit)) {
+            Some(Some((t, door_face, door_slide))) => {
+              (distance, face, slide) = (t, door_face, door_slide);
+              HitKind::Wall
+            }
+            Some(None) => HitKind::Open,
+            None => HitKind::Wall, // sin estado: como una pared normal
+          }
+        }
+        Some(OPEN_DOOR_CELL) => HitKind::Open,
+        Some(c) if is_transparent(c) => HitKind::Transparent,
+        Some(c) if c != ' ' && !is_marker(c) => HitKind::Wall,
+        _ => HitKind::Open,
+      }
+    };
+    self.done = matches!(kind, HitKind::NoHit | HitKind::OutOfBounds);
+
+    let distance = distance.min(self.max_distance);
+    let hit = Vector2::new(self.origin.x + self.dir.x * distance, self.origin.y + self.dir.y * distance);
+    let (i, j) = (map_y.max(0) as usize, map_x.max(0) as usize);
+    let impact = if matches!(kind, HitKind::Wall | HitKind::Transparent) { self.maze[i][j] } else { ' ' };
+
+    Some(Intersect {
+      kind,
+      distance,
+      impact,
+      hit,
+      face,
+      cell: (i, j),
+      slide,
+    })
+  }
+}
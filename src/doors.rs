@@ -0,0 +1,174 @@
+// doors.rs
+
+//! Puertas corredizas. En el mapa son celdas `DOOR_CELL` con una hoja delgada en medio;
+//! se abren cuando alguien se acerca o el jugador usa la acción de usar, y se cierran
+//! solas después de un rato.
+//!
+//! El estado vive en `Doors` y se refleja en el mapa: mientras la hoja no esté corrida del todo
+//! la celda es `DOOR_CELL` (pared para `is_wall`); abierta es `OPEN_DOOR_CELL` (se pasa).
+//! Los rayos chocan con la hoja según cuánto se ha corrido (ver `walk_ray`).
+//!
+//! Las puertas con cerradura (`LOCKED_DOORS`) no se abren hasta que el jugador recoge
+//! su llave; mientras tanto conservan su carácter en el mapa.
+
+use raylib::prelude::Vector2;
+use std::collections::HashMap;
+
+use crate::maze::{circle_overlaps_cell, is_wall, Maze};
+
+/// Puerta (cerrada o moviéndose).
+pub const DOOR_CELL: char = 'D';
+/// Puerta abierta del todo.
+pub const OPEN_DOOR_CELL: char = 'd';
+
+/// Puertas con cerradura y la llave (objeto del mapa) que las abre.
+pub const LOCKED_DOORS: &[(char, char)] = &[('K', 'k'), ('J', 'j')];
+
+/// Fracción de la hoja que se corre por segundo.
+const SLIDE_SPEED: f32 = 1.6;
+/// Segundos que se queda abierta antes de cerrarse.
+const HOLD_TIME: f32 = 3.0;
+/// Distancia (en celdas, al centro de la puerta) a la que se abre sola.
+const AUTO_RANGE: f32 = 1.1;
+/// Distancia (en celdas) que alcanza la acción de usar.
+const USE_RANGE: f32 = 1.8;
+
+/// ¿Es una celda de puerta (abierta o no)?
+pub fn is_door(c: char) -> bool {
+    is_closed_door(c) || c == OPEN_DOOR_CELL
+}
+
+/// ¿Es una puerta con la hoja puesta (cerrada, moviéndose o con cerradura)?
+pub fn is_closed_door(c: char) -> bool {
+    c == DOOR_CELL || lock_key(c).is_some()
+}
+
+/// Llave que pide una puerta con cerradura.
+pub fn lock_key(c: char) -> Option<char> {
+    LOCKED_DOORS.iter().find(|&&(door, _)| door == c).map(|&(_, key)| key)
+}
+
+#[derive(Clone, Debug)]
+pub struct Door {
+    pub along_x: bool, // la hoja va de izquierda a derecha (paredes a los lados en x)
+    pub open: f32,     // 0 = cerrada, 1 = abierta; la hoja cubre [open, 1] de la celda
+    opening: bool,
+    hold: f32,         // segundos que faltan para empezar a cerrarse
+    pub lock: Option<char>, // llave que falta para abrirla
+}
+
+/// Estado de todas las puertas de un mapa, por celda (fila, columna).
+#[derive(Clone, Debug, Default)]
+pub struct Doors {
+    doors: HashMap<(usize, usize), Door>,
+}
+
+impl Doors {
+    /// Puertas del mapa, todas cerradas. La hoja se orienta según las paredes vecinas.
+    pub fn from_maze(maze: &Maze) -> Self {
+        let mut doors = HashMap::new();
+        for (i, row) in maze.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                if !is_door(c) { continue; }
+                let left = j.checked_sub(1).is_some_and(|j| is_wall(maze, i, j));
+                let along_x = left && is_wall(maze, i, j + 1);
+                let door = Door { along_x, open: 0.0, opening: false, hold: 0.0, lock: lock_key(c) };
+                doors.insert((i, j), door);
+            }
+        }
+        Self { doors }
+    }
+
+    pub fn get(&self, cell: (usize, usize)) -> Option<&Door> {
+        self.doors.get(&cell)
+    }
+
+    /// Quita la cerradura a las puertas que abre `key`.
+    pub fn unlock(&mut self, key: char) {
+        for door in self.doors.values_mut().filter(|door| door.lock == Some(key)) {
+            door.lock = None;
+        }
+    }
+
+    /// Llave que pide la puerta con cerradura más cercana a `pos`, si hay una al alcance.
+    pub fn locked_near(&self, pos: Vector2, block_size: usize) -> Option<char> {
+        let bs = block_size as f32;
+        self.doors
+            .iter()
+            .filter_map(|(&cell, door)| {
+                let center = cell_center(cell, bs);
+                Some(((pos.x - center.x).hypot(pos.y - center.y), door.lock?))
+            })
+            .filter(|&(dist, _)| dist < USE_RANGE * bs)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, key)| key)
+    }
+
+    /// Abre la puerta más cercana que esté enfrente (`angle`) y al alcance.
+    /// Devuelve `true` si había alguna (las que tienen cerradura no cuentan).
+    pub fn use_from(&mut self, pos: Vector2, angle: f32, block_size: usize) -> bool {
+        let bs = block_size as f32;
+        let facing = Vector2::new(angle.cos(), angle.sin());
+        let target = self
+            .doors
+            .iter_mut()
+            .filter(|(_, door)| door.lock.is_none())
+            .map(|(&cell, door)| {
+                let (dx, dy) = (cell_center(cell, bs).x - pos.x, cell_center(cell, bs).y - pos.y);
+                let dist = (dx * dx + dy * dy).sqrt().max(0.0001);
+                (dist, (dx * facing.x + dy * facing.y) / dist, door)
+            })
+            .filter(|&(dist, cos, _)| dist < USE_RANGE * bs && cos > 0.5)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        match target {
+            Some((_, _, door)) => {
+                door.opening = true;
+                door.hold = HOLD_TIME;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Avanza un cuadro: abre las puertas que tienen cerca a alguien de `openers`, corre las hojas
+    /// y cierra las que ya esperaron, salvo que algún círculo de `occupants` (centro, radio)
+    /// esté en el hueco. Deja cada celda como `DOOR_CELL` u `OPEN_DOOR_CELL` en `maze`;
+    /// las que siguen con cerradura no se tocan.
+    pub fn update(
+        &mut self,
+        maze: &mut Maze,
+        dt: f32,
+        openers: &[Vector2],
+        occupants: &[(Vector2, f32)],
+        block_size: usize,
+    ) {
+        let bs = block_size as f32;
+        for (&(i, j), door) in self.doors.iter_mut().filter(|(_, door)| door.lock.is_none()) {
+            let center = cell_center((i, j), bs);
+            let near = openers.iter().any(|p| (p.x - center.x).hypot(p.y - center.y) < AUTO_RANGE * bs);
+            if near {
+                door.opening = true;
+                door.hold = HOLD_TIME;
+            }
+
+            if door.opening {
+                door.open = (door.open + SLIDE_SPEED * dt).min(1.0);
+                if door.open >= 1.0 {
+                    door.hold -= dt;
+                    let blocked = occupants.iter().any(|&(p, r)| circle_overlaps_cell(p, r, (i, j), block_size));
+                    if door.hold <= 0.0 && !blocked {
+                        door.opening = false;
+                    }
+                }
+            } else {
+                door.open = (door.open - SLIDE_SPEED * dt).max(0.0);
+            }
+
+            maze[i][j] = if door.open >= 1.0 { OPEN_DOOR_CELL } else { DOOR_CELL };
+        }
+    }
+}
+
+fn cell_center((i, j): (usize, usize), bs: f32) -> Vector2 {
+    Vector2::new((j as f32 + 0.5) * bs, (i as f32 + 0.5) * bs)
+}
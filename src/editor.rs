@@ -0,0 +1,116 @@
+// editor.rs
+
+//! Editor de niveles: cambia celdas y el spawn sobre una copia del nivel,
+//! con deshacer/rehacer, y lo guarda en el mismo formato `.txt` que se carga.
+
+use std::io;
+
+use crate::doors::LOCKED_DOORS;
+use crate::level::{save_level, Level};
+use crate::maze::{is_wall, BASE_CELLS};
+use crate::pickup::PICKUP_CELLS;
+
+/// Tipos de celda que se recorren con cada clic, en orden: las fijas,
+/// las puertas con cerradura y los objetos.
+pub fn editor_cells() -> Vec<char> {
+    let locked = LOCKED_DOORS.iter().map(|&(door, _)| door);
+    BASE_CELLS.iter().copied().chain(locked).chain(PICKUP_CELLS.iter().copied()).collect()
+}
+
+/// Un cambio reversible.
+#[derive(Clone, Copy, Debug)]
+enum Edit {
+    Cell { cell: (usize, usize), before: char, after: char },
+    Spawn { before: Option<(usize, usize)>, after: Option<(usize, usize)> },
+}
+
+pub struct Editor {
+    pub level: Level,
+    pub path: String,           // dónde se guarda
+    pub status: Option<String>, // último mensaje para la UI (guardado, errores)
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    saved_at: Option<usize>, // largo de `undo` al guardar; None si ese estado ya no se alcanza
+}
+
+impl Editor {
+    pub fn new(level: Level, path: &str) -> Self {
+        Self { level, path: path.to_string(), status: None, undo: Vec::new(), redo: Vec::new(), saved_at: Some(0) }
+    }
+
+    /// Hay cambios sin guardar.
+    pub fn is_dirty(&self) -> bool {
+        self.saved_at != Some(self.undo.len())
+    }
+
+    /// Pasa la celda (fila, columna) al siguiente tipo de `editor_cells`.
+    /// Las filas cortas se rellenan con espacios hasta la celda.
+    pub fn cycle_cell(&mut self, cell: (usize, usize)) {
+        let (i, j) = cell;
+        let Some(row) = self.level.maze.get(i) else { return; };
+        let before = row.get(j).copied().unwrap_or(' ');
+        let cells = editor_cells();
+        let k = cells.iter().position(|&c| c == before).map_or(0, |k| k + 1);
+        let after = cells[k % cells.len()];
+        self.apply(Edit::Cell { cell, before, after });
+    }
+
+    /// Pone el spawn en una celda transitable.
+    pub fn set_spawn(&mut self, cell: (usize, usize)) {
+        if is_wall(&self.level.maze, cell.0, cell.1) {
+            self.status = Some("El spawn debe estar en una celda libre".to_string());
+            return;
+        }
+        self.apply(Edit::Spawn { before: self.level.spawn, after: Some(cell) });
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(edit) = self.undo.pop() {
+            self.write(edit, false);
+            self.redo.push(edit);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(edit) = self.redo.pop() {
+            self.write(edit, true);
+            self.undo.push(edit);
+        }
+    }
+
+    /// Guarda el nivel en `path`.
+    pub fn save(&mut self) -> io::Result<()> {
+        save_level(&self.path, &self.level)?;
+        self.saved_at = Some(self.undo.len());
+        self.status = Some(format!("Guardado en {}", self.path));
+        Ok(())
+    }
+
+    /// Aplica un cambio nuevo: descarta lo que se podía rehacer.
+    fn apply(&mut self, edit: Edit) {
+        // si lo guardado estaba entre lo que se podía rehacer, ya no se puede volver ahí
+        if self.saved_at.is_some_and(|depth| depth > self.undo.len()) {
+            self.saved_at = None;
+        }
+        self.write(edit, true);
+        self.undo.push(edit);
+        self.redo.clear();
+        self.status = None;
+    }
+
+    /// Escribe el estado `after` (o `before` al deshacer) de un cambio.
+    fn write(&mut self, edit: Edit, forward: bool) {
+        match edit {
+            Edit::Cell { cell: (i, j), before, after } => {
+                let row = &mut self.level.maze[i];
+                if row.len() <= j {
+                    row.resize(j + 1, ' ');
+                }
+                row[j] = if forward { after } else { before };
+            }
+            Edit::Spawn { before, after } => {
+                self.level.spawn = if forward { after } else { before };
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// error.rs
+
+use std::fmt;
+use std::io;
+
+/// Errores al cargar niveles e imágenes.
+/// Siempre llevan la ruta del archivo; los de contenido también la línea y columna (desde 1).
+#[derive(Debug)]
+pub enum LoadError {
+    /// No se pudo abrir o leer el archivo.
+    Io { path: String, source: io::Error },
+    /// Carácter que no corresponde a ningún tipo de celda.
+    BadChar { path: String, line: usize, column: usize, ch: char },
+    /// Línea que no se entiende: `@clave: valor` de un nivel o `accion = entradas` de los controles.
+    BadHeader { path: String, line: usize, message: String },
+    /// El archivo no tiene ninguna fila de laberinto.
+    Empty { path: String },
+    /// Imagen que raylib no pudo cargar (textura o sprite).
+    Image { path: String, reason: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => write!(f, "{}: no se pudo leer ({})", path, source),
+            LoadError::BadChar { path, line, column, ch } => {
+                write!(f, "{}:{}:{}: caracter desconocido {:?}", path, line, column, ch)
+            }
+            LoadError::BadHeader { path, line, message } => write!(f, "{}:{}: {}", path, line, message),
+            LoadError::Empty { path } => write!(f, "{}: el mapa esta vacio", path),
+            LoadError::Image { path, reason } => write!(f, "{}: no se pudo cargar la imagen ({})", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// export.rs
+
+//! Escritura de imágenes sin raylib (PPM y PNG sin comprimir),
+//! para guardar cuadros renderizados sin ventana ni GPU.
+
+use raylib::prelude::Color;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Guarda los píxeles como PPM binario (P6). Se descarta el alpha.
+pub fn write_ppm(path: &str, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for c in pixels {
+        out.write_all(&[c.r, c.g, c.b])?;
+    }
+    out.flush()
+}
+
+/// Guarda los píxeles como PNG RGBA de 8 bits.
+/// Usa bloques deflate "stored" (sin compresión): archivos grandes pero sin dependencias.
+pub fn write_png(path: &str, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    // Filas con byte de filtro 0 (None) al inicio
+    let mut raw = Vec::with_capacity((width as usize * 4 + 1) * height as usize);
+    for row in pixels.chunks(width.max(1) as usize) {
+        raw.push(0);
+        for c in row {
+            raw.extend_from_slice(&[c.r, c.g, c.b, c.a]);
+        }
+    }
+
+    // zlib: cabecera, bloques stored de hasta 65535 bytes y adler32
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bits, RGBA, deflate, filtro 0, sin entrelazado
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A])?;
+    write_chunk(&mut out, b"IHDR", &ihdr)?;
+    write_chunk(&mut out, b"IDAT", &zlib)?;
+    write_chunk(&mut out, b"IEND", &[])?;
+    out.flush()
+}
+
+/// Escribe un chunk PNG: longitud, tipo, datos y CRC32 de tipo+datos.
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data.iter()).copied());
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32(bytes: impl Iterator<Item = u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in bytes {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -1,37 +1,111 @@
 // framebuffer.rs
 
 use raylib::prelude::*;
+use std::io;
 
+use crate::export::{write_png, write_ppm};
+
+/// Buffer de color en memoria (CPU). Se puede dibujar y exportar sin ventana;
+/// raylib sólo se usa al presentarlo en pantalla.
+/// La textura de GPU se crea una vez y se actualiza en cada cuadro.
+/// El buffer puede ser más pequeño que la ventana: al presentarlo se escala.
 pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
-    pub color_buffer: Image,
+    pub color_buffer: Vec<Color>,
+    pub integer_scaling: bool, // escalar sólo por factores enteros (pixel perfect)
     background_color: Color,
     current_color: Color,
+    texture: Option<Texture2D>,
+    upload_buffer: Vec<u8>,
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32) -> Self {
-        let color_buffer = Image::gen_image_color(width as i32, height as i32, Color::BLACK);
+        let color_buffer = vec![Color::BLACK; (width * height) as usize];
         Framebuffer {
             width,
             height,
             color_buffer,
+            integer_scaling: false,
             background_color: Color::BLACK,
             current_color: Color::WHITE,
+            texture: None,
+            upload_buffer: Vec::new(),
+        }
+    }
+
+    /// Cambia la resolución interna; el contenido se descarta.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == self.width && height == self.height {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.color_buffer = vec![self.background_color; (width * height) as usize];
+    }
+
+    /// Rectángulo de la ventana donde se dibuja el buffer.
+    /// Con `integer_scaling` usa el mayor factor entero que cabe y centra el resultado;
+    /// si no, estira el buffer a toda la ventana.
+    pub fn present_rect(&self, screen_w: i32, screen_h: i32) -> Rectangle {
+        let (w, h) = (self.width.max(1) as i32, self.height.max(1) as i32);
+        if self.integer_scaling {
+            let k = (screen_w / w).min(screen_h / h).max(1);
+            let (dw, dh) = (w * k, h * k);
+            Rectangle::new(((screen_w - dw) / 2) as f32, ((screen_h - dh) / 2) as f32, dw as f32, dh as f32)
+        } else {
+            Rectangle::new(0.0, 0.0, screen_w as f32, screen_h as f32)
+        }
+    }
+
+    /// Convierte una posición de la ventana (p. ej. el mouse) a coordenadas del buffer.
+    pub fn screen_to_buffer(&self, pos: Vector2, screen_w: i32, screen_h: i32) -> Option<(u32, u32)> {
+        let r = self.present_rect(screen_w, screen_h);
+        if r.width <= 0.0 || r.height <= 0.0 { return None; }
+        let x = (pos.x - r.x) / r.width * self.width as f32;
+        let y = (pos.y - r.y) / r.height * self.height as f32;
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
         }
+        Some((x as u32, y as u32))
     }
 
     pub fn clear(&mut self) {
-        self.color_buffer = Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        self.color_buffer.fill(self.background_color);
     }
 
     pub fn set_pixel(&mut self, x: u32, y: u32) {
         if x < self.width && y < self.height {
-            self.color_buffer.draw_pixel(x as i32, y as i32, self.current_color);
+            let idx = (y * self.width + x) as usize;
+            self.color_buffer[idx] = self.current_color;
+        }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.color_buffer[(y * self.width + x) as usize])
+        } else {
+            None
         }
     }
 
+    /// Buffer de píxeles crudo (fila por fila, `width` colores por fila).
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.color_buffer
+    }
+
+    /// Divide el buffer en franjas de `rows` filas, cada una con el índice de su primera fila.
+    /// Las franjas no se solapan, así que se pueden pintar en hilos distintos.
+    pub fn row_bands_mut(&mut self, rows: usize) -> impl Iterator<Item = (usize, &mut [Color])> {
+        let rows = rows.max(1);
+        let stride = self.width.max(1) as usize * rows;
+        self.color_buffer
+            .chunks_mut(stride)
+            .enumerate()
+            .map(move |(k, band)| (k * rows, band))
+    }
+
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
@@ -40,49 +114,94 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn _render_to_file(&self, file_path: &str) {
-        self.color_buffer.export_image(file_path);
+    /// Guarda el buffer en disco; el formato sale de la extensión (.png o .ppm).
+    pub fn render_to_file(&self, file_path: &str) -> io::Result<()> {
+        if file_path.to_ascii_lowercase().ends_with(".ppm") {
+            write_ppm(file_path, self.width, self.height, &self.color_buffer)
+        } else {
+            write_png(file_path, self.width, self.height, &self.color_buffer)
+        }
+    }
+
+    /// Sube los píxeles a la textura de GPU, creándola sólo la primera vez
+    /// (o si cambió el tamaño del buffer).
+    fn upload(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) -> Result<(), String> {
+        let stale = match &self.texture {
+            Some(tex) => tex.width != self.width as i32 || tex.height != self.height as i32,
+            None => true,
+        };
+        if stale {
+            self.texture = None;
+            let blank = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+            let texture = window
+                .load_texture_from_image(raylib_thread, &blank)
+                .map_err(|e| format!("no se pudo crear la textura del framebuffer: {}", e))?;
+            // vecino más cercano: al escalar se ven píxeles nítidos
+            texture.set_texture_filter(raylib_thread, TextureFilter::TEXTURE_FILTER_POINT);
+            self.texture = Some(texture);
+        }
+
+        self.upload_buffer.clear();
+        self.upload_buffer.extend(self.color_buffer.iter().flat_map(|c| [c.r, c.g, c.b, c.a]));
+
+        if let Some(texture) = self.texture.as_mut() {
+            texture
+                .update_texture(&self.upload_buffer)
+                .map_err(|e| format!("no se pudo actualizar la textura del framebuffer: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Dibuja la textura del buffer escalada al rectángulo `dest`.
+    fn draw_scaled(&self, d: &mut RaylibDrawHandle, texture: &Texture2D, dest: Rectangle) {
+        let src = Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32);
+        d.draw_texture_pro(texture, src, dest, Vector2::new(0.0, 0.0), 0.0, Color::WHITE);
     }
 
     pub fn swap_buffers(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
-    ) {
-        // 1) Cargar la textura en una variable aparte (salimos del if let)
-        let texture = match window.load_texture_from_image(raylib_thread, &self.color_buffer) {
-            Ok(tex) => tex,
-            Err(_) => return, // si falla, salimos silenciosamente
-        };
+    ) -> Result<(), String> {
+        // 1) Sube el buffer a la textura persistente
+        self.upload(window, raylib_thread)?;
 
-        // 2) Lee FPS ANTES de begin_drawing (evita doble préstamo)
+        // 2) Lee FPS y tamaño ANTES de begin_drawing (evita doble préstamo)
         let fps = window.get_fps();
+        let dest = self.present_rect(window.get_screen_width(), window.get_screen_height());
 
         // 3) dibuja
         let mut renderer = window.begin_drawing(raylib_thread);
-        // renderer.clear_background(Color::BLACK); // opcional
-        renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+        renderer.clear_background(Color::BLACK);
+        if let Some(texture) = &self.texture {
+            self.draw_scaled(&mut renderer, texture, dest);
+        }
         renderer.draw_text(&format!("FPS: {}", fps), 10, 10, 20, Color::LIME);
         // renderer se libera aquí automáticamente
+        Ok(())
     }
 
+    /// Presenta el buffer y luego dibuja la UI encima.
+    /// Si falla la subida a GPU se dibuja igual la UI y se devuelve el error.
     pub fn present_with_ui<F: FnOnce(&mut RaylibDrawHandle)>(
-        &self,
+        &mut self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
         ui: F,
-    ) {
-        let texture = match window.load_texture_from_image(raylib_thread, &self.color_buffer) {
-            Ok(tex) => tex,
-            Err(_) => return,
-        };
+    ) -> Result<(), String> {
+        let uploaded = self.upload(window, raylib_thread);
         let fps = window.get_fps();
+        let dest = self.present_rect(window.get_screen_width(), window.get_screen_height());
 
         let mut d = window.begin_drawing(raylib_thread);
-        d.draw_texture(&texture, 0, 0, Color::WHITE);
+        d.clear_background(Color::BLACK); // bandas negras con escalado entero
+        if let Some(texture) = &self.texture {
+            self.draw_scaled(&mut d, texture, dest);
+        }
         d.draw_text(&format!("FPS: {}", fps), 10, 10, 20, Color::LIME);
 
         // 👇 Aquí dibujamos overlays de acuerdo al estado (desde main.rs)
         ui(&mut d);
+        uploaded
     }
 }
@@ -0,0 +1,300 @@
+// generator.rs
+
+//! Generador de laberintos con el mismo formato de texto de los niveles.
+//!
+//! Cada celda lógica ocupa 3 columnas y 2 filas del `Maze`:
+//!
+//! ```text
+//! +--+--+
+//! |     |
+//! +  +--+
+//! |    g|
+//! +--+--+
+//! ```
+//!
+//! La misma configuración (incluida la semilla) siempre produce el mismo laberinto.
+
+use crate::maze::{Maze, MOTO_CELL};
+
+/// Algoritmo para tallar los pasillos.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// DFS con retroceso: pasillos largos y pocas bifurcaciones.
+    Backtracker,
+    /// Prim aleatorio: muchas bifurcaciones cortas.
+    Prim,
+    /// Kruskal aleatorio: ramas uniformes en todo el mapa.
+    Kruskal,
+}
+
+impl Algorithm {
+    /// Siguiente algoritmo (para cambiarlo en el menú).
+    pub fn next(self) -> Self {
+        match self {
+            Algorithm::Backtracker => Algorithm::Prim,
+            Algorithm::Prim => Algorithm::Kruskal,
+            Algorithm::Kruskal => Algorithm::Backtracker,
+        }
+    }
+
+    /// Nombre corto para la UI.
+    pub fn label(self) -> &'static str {
+        match self {
+            Algorithm::Backtracker => "backtracker",
+            Algorithm::Prim => "prim",
+            Algorithm::Kruskal => "kruskal",
+        }
+    }
+}
+
+/// Dónde se pone la meta 'g'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoalPlacement {
+    /// En la celda más lejana (caminando) desde el inicio.
+    Farthest,
+    /// En la esquina opuesta al inicio.
+    OppositeCorner,
+}
+
+/// Parámetros del generador. El jugador empieza en la celda (0, 0),
+/// que coincide con el spawn por defecto de los niveles.
+#[derive(Clone, Copy, Debug)]
+pub struct MazeConfig {
+    pub width: usize,  // celdas lógicas
+    pub height: usize, // celdas lógicas
+    pub seed: u64,
+    pub algorithm: Algorithm,
+    pub goal: GoalPlacement,
+    pub motos: usize,  // motos enemigas (marcadores `MOTO_CELL`)
+}
+
+impl MazeConfig {
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        Self {
+            width: width.max(1),
+            height: height.max(1),
+            seed,
+            algorithm: Algorithm::Backtracker,
+            goal: GoalPlacement::Farthest,
+            motos: 3,
+        }
+    }
+}
+
+/// PRNG pequeño (SplitMix64) para no depender de crates externos
+/// y para que la semilla dé el mismo resultado en cualquier plataforma.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Entero en `0..n` (n > 0).
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for k in (1..items.len()).rev() {
+            items.swap(k, self.below(k + 1));
+        }
+    }
+}
+
+/// Pasillos abiertos entre celdas lógicas.
+struct Grid {
+    width: usize,
+    height: usize,
+    open_east: Vec<bool>,  // celda -> celda a la derecha
+    open_south: Vec<bool>, // celda -> celda de abajo
+}
+
+impl Grid {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            open_east: vec![false; width * height],
+            open_south: vec![false; width * height],
+        }
+    }
+
+    /// Vecinos en 4 direcciones de la celda `c` (índice fila * ancho + columna).
+    fn neighbors(&self, c: usize) -> Vec<usize> {
+        let (r, col) = (c / self.width, c % self.width);
+        let mut out = Vec::with_capacity(4);
+        if r > 0 { out.push(c - self.width); }
+        if r + 1 < self.height { out.push(c + self.width); }
+        if col > 0 { out.push(c - 1); }
+        if col + 1 < self.width { out.push(c + 1); }
+        out
+    }
+
+    /// Abre la pared entre dos celdas vecinas.
+    fn carve(&mut self, a: usize, b: usize) {
+        let (lo, hi) = (a.min(b), a.max(b));
+        if hi == lo + 1 {
+            self.open_east[lo] = true;
+        } else {
+            self.open_south[lo] = true;
+        }
+    }
+
+    fn is_open(&self, a: usize, b: usize) -> bool {
+        let (lo, hi) = (a.min(b), a.max(b));
+        if hi == lo + 1 { self.open_east[lo] } else { self.open_south[lo] }
+    }
+}
+
+/// Pasos (por los pasillos) que debe haber entre el inicio y una moto.
+const MOTO_SAFE_STEPS: usize = 3;
+/// Separa la semilla de las motos de la del laberinto.
+const MOTO_STREAM: u64 = 0x6D6F_746F_7321;
+
+/// Genera un laberinto perfecto (un solo camino entre dos celdas) con la meta colocada.
+pub fn generate(config: &MazeConfig) -> Maze {
+    let (w, h) = (config.width.max(1), config.height.max(1));
+    let mut rng = Rng(config.seed);
+    let mut grid = Grid::new(w, h);
+
+    match config.algorithm {
+        Algorithm::Backtracker => carve_backtracker(&mut grid, &mut rng),
+        Algorithm::Prim => carve_prim(&mut grid, &mut rng),
+        Algorithm::Kruskal => carve_kruskal(&mut grid, &mut rng),
+    }
+
+    let goal = match config.goal {
+        GoalPlacement::Farthest => farthest_cell(&grid, 0),
+        GoalPlacement::OppositeCorner => w * h - 1,
+    };
+    let mut maze = to_maze(&grid, goal);
+
+    // Motos en celdas al azar, a más de `MOTO_SAFE_STEPS` pasos del inicio y fuera de la meta.
+    // Usan su propio generador para que cada semilla siga dando el mismo laberinto.
+    let (steps, _) = cell_distances(&grid, 0);
+    let mut free: Vec<usize> = (0..w * h).filter(|&c| c != goal && steps[c] > MOTO_SAFE_STEPS).collect();
+    Rng(config.seed ^ MOTO_STREAM).shuffle(&mut free);
+    for &c in free.iter().take(config.motos) {
+        maze[c / w * 2 + 1][c % w * 3 + 1] = MOTO_CELL;
+    }
+    maze
+}
+
+fn carve_backtracker(grid: &mut Grid, rng: &mut Rng) {
+    let mut visited = vec![false; grid.width * grid.height];
+    let mut stack = vec![0];
+    visited[0] = true;
+    while let Some(&c) = stack.last() {
+        let free: Vec<usize> = grid.neighbors(c).into_iter().filter(|&n| !visited[n]).collect();
+        if free.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let n = free[rng.below(free.len())];
+        grid.carve(c, n);
+        visited[n] = true;
+        stack.push(n);
+    }
+}
+
+fn carve_prim(grid: &mut Grid, rng: &mut Rng) {
+    let mut inside = vec![false; grid.width * grid.height];
+    let mut frontier: Vec<(usize, usize)> = Vec::new(); // (celda dentro, vecina fuera)
+    inside[0] = true;
+    frontier.extend(grid.neighbors(0).into_iter().map(|n| (0, n)));
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.below(frontier.len()));
+        if inside[to] { continue; }
+        grid.carve(from, to);
+        inside[to] = true;
+        frontier.extend(grid.neighbors(to).into_iter().filter(|&n| !inside[n]).map(|n| (to, n)));
+    }
+}
+
+fn carve_kruskal(grid: &mut Grid, rng: &mut Rng) {
+    let n = grid.width * grid.height;
+    let mut edges: Vec<(usize, usize)> = (0..n)
+        .flat_map(|c| grid.neighbors(c).into_iter().filter(move |&d| d > c).map(move |d| (c, d)))
+        .collect();
+    rng.shuffle(&mut edges);
+
+    let mut parent: Vec<usize> = (0..n).collect();
+    fn root(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    for (a, b) in edges {
+        let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+        if ra != rb {
+            parent[ra] = rb;
+            grid.carve(a, b);
+        }
+    }
+}
+
+/// Celda más lejana desde `start` siguiendo los pasillos (BFS).
+fn farthest_cell(grid: &Grid, start: usize) -> usize {
+    cell_distances(grid, start).1
+}
+
+/// Pasos desde `start` hasta cada celda siguiendo los pasillos (BFS; `usize::MAX` si no se llega)
+/// y la última celda que alcanza la búsqueda.
+fn cell_distances(grid: &Grid, start: usize) -> (Vec<usize>, usize) {
+    let mut dist = vec![usize::MAX; grid.width * grid.height];
+    let mut queue = std::collections::VecDeque::from([start]);
+    dist[start] = 0;
+    let mut last = start;
+    while let Some(c) = queue.pop_front() {
+        last = c;
+        for n in grid.neighbors(c) {
+            if dist[n] == usize::MAX && grid.is_open(c, n) {
+                dist[n] = dist[c] + 1;
+                queue.push_back(n);
+            }
+        }
+    }
+    (dist, last)
+}
+
+/// Convierte los pasillos al texto de celdas: `+` en las esquinas, `--` y `|` en las paredes.
+fn to_maze(grid: &Grid, goal: usize) -> Maze {
+    let (w, h) = (grid.width, grid.height);
+    let mut maze: Maze = vec![vec![' '; w * 3 + 1]; h * 2 + 1];
+
+    for (i, row) in maze.iter_mut().enumerate() {
+        for k in 0..=w {
+            if i % 2 == 0 {
+                row[k * 3] = '+';
+            }
+        }
+    }
+    for r in 0..=h {
+        for c in 0..w {
+            let closed = r == 0 || r == h || !grid.open_south[(r - 1) * w + c];
+            if closed {
+                maze[r * 2][c * 3 + 1] = '-';
+                maze[r * 2][c * 3 + 2] = '-';
+            }
+        }
+    }
+    for r in 0..h {
+        for k in 0..=w {
+            let closed = k == 0 || k == w || !grid.open_east[r * w + k - 1];
+            if closed {
+                maze[r * 2 + 1][k * 3] = '|';
+            }
+        }
+    }
+
+    let (gr, gc) = (goal / w, goal % w);
+    maze[gr * 2 + 1][gc * 3 + 2] = 'g';
+    maze
+}
@@ -0,0 +1,100 @@
+// heights.rs
+
+//! Capa de alturas del mapa: cuánto mide la pared de cada celda y a qué altura está su piso.
+//!
+//! Va en el archivo de nivel después de la cuadrícula, en secciones que la copian celda por celda:
+//!
+//! ```text
+//! +--+--+--+
+//! |  +    g|
+//! +--+--+--+
+//! @heights
+//! ...2......
+//! @floors
+//! .....22...
+//! ```
+//!
+//! Cada dígito son cuartos de la altura normal de una pared (4 = normal, 2 = media, 8 = doble).
+//! `.` o espacio deja el valor por defecto (pared 4, piso 0). En `@heights` sólo cuentan
+//! las celdas de pared; `@floors` sube el piso de cualquier celda (las paredes quedan encima).
+
+use crate::doors::is_door;
+use crate::maze::{cell_at, is_marker, is_transparent, Maze};
+
+/// Altura de una pared normal (unidad de las alturas).
+pub const WALL_HEIGHT: f32 = 1.0;
+/// Cuartos por unidad de altura en el archivo.
+const QUARTERS: f32 = 4.0;
+
+/// Valores de una sección, en cuartos (`None` = por defecto).
+pub type Layer = Vec<Vec<Option<u8>>>;
+
+/// Alturas por celda. Vacío = todas las paredes normales y el piso en 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Heights {
+    pub walls: Layer,
+    pub floors: Layer,
+}
+
+impl Heights {
+    /// `true` si no cambia nada respecto al mapa plano.
+    pub fn is_flat(&self) -> bool {
+        let empty = |layer: &Layer| layer.iter().flatten().all(Option::is_none);
+        empty(&self.walls) && empty(&self.floors)
+    }
+
+    /// Altura de la pared de la celda (fila, columna).
+    pub fn wall(&self, i: usize, j: usize) -> f32 {
+        quarters(&self.walls, i, j).map_or(WALL_HEIGHT, |q| q as f32 / QUARTERS)
+    }
+
+    /// Altura del piso de la celda (fila, columna).
+    pub fn floor(&self, i: usize, j: usize) -> f32 {
+        quarters(&self.floors, i, j).map_or(0.0, |q| q as f32 / QUARTERS)
+    }
+
+    /// Altura de lo más alto de la celda: su piso, más la pared si es opaca para los rayos.
+    /// Las puertas cuentan como piso: su hoja está en medio de la celda.
+    pub fn top(&self, maze: &Maze, i: usize, j: usize) -> f32 {
+        match cell_at(maze, i, j) {
+            Some(c) if c != ' ' && !is_transparent(c) && !is_door(c) && !is_marker(c) => {
+                self.floor(i, j) + self.wall(i, j)
+            }
+            _ => self.floor(i, j),
+        }
+    }
+
+    /// La celda más alta del mapa: un rayo que choca con algo así de alto ya no ve nada detrás.
+    pub fn max_top(&self, maze: &Maze) -> f32 {
+        maze.iter()
+            .enumerate()
+            .flat_map(|(i, row)| (0..row.len()).map(move |j| (i, j)))
+            .map(|(i, j)| self.top(maze, i, j))
+            .fold(WALL_HEIGHT, f32::max)
+    }
+}
+
+fn quarters(layer: &Layer, i: usize, j: usize) -> Option<u8> {
+    layer.get(i).and_then(|row| row.get(j)).copied().flatten()
+}
+
+/// Lee una fila de sección; `Err(columna)` (desde 0) con el primer carácter inválido.
+pub fn parse_layer_row(line: &str) -> Result<Vec<Option<u8>>, usize> {
+    line.chars()
+        .enumerate()
+        .map(|(col, ch)| match ch {
+            '.' | ' ' => Ok(None),
+            _ => ch.to_digit(10).map(|d| Some(d as u8)).ok_or(col),
+        })
+        .collect()
+}
+
+/// Texto de una sección (sin la línea `@heights`/`@floors`).
+pub fn layer_to_text(layer: &Layer) -> String {
+    let mut out = String::new();
+    for row in layer {
+        out.extend(row.iter().map(|q| q.map_or('.', |d| char::from(b'0' + d))));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,380 @@
+// input.rs
+
+//! Capa de entrada: teclas, botones del mouse y del gamepad se traducen a acciones.
+//! Las asignaciones se leen de un archivo de texto y se pueden cambiar en el menú de controles.
+//!
+//! ```text
+//! # acción = entrada, entrada, ...
+//! forward = W, UP, PAD_UP, LSTICK_UP
+//! sprint  = LEFT_SHIFT, PAD_LB
+//! ```
+
+use raylib::prelude::*;
+use std::fs;
+use std::io;
+
+use crate::error::LoadError;
+
+/// Archivo de controles por defecto (relativo al directorio del juego).
+pub const BINDINGS_FILE: &str = "assets/config/bindings.txt";
+
+/// Zona muerta de los sticks analógicos.
+const STICK_DEADZONE: f32 = 0.25;
+
+/// Acciones del jugador.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Forward,
+    Back,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    Sprint,
+    ToggleMap,
+    Use,
+}
+
+/// Todas las acciones, en el orden del menú y del archivo.
+pub const ACTIONS: [Action; 9] = [
+    Action::Forward,
+    Action::Back,
+    Action::StrafeLeft,
+    Action::StrafeRight,
+    Action::TurnLeft,
+    Action::TurnRight,
+    Action::Sprint,
+    Action::ToggleMap,
+    Action::Use,
+];
+
+impl Action {
+    /// Nombre en el archivo de controles.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Forward => "forward",
+            Action::Back => "back",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::Sprint => "sprint",
+            Action::ToggleMap => "toggle_map",
+            Action::Use => "use",
+        }
+    }
+
+    /// Nombre para la UI.
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Forward => "Avanzar",
+            Action::Back => "Retroceder",
+            Action::StrafeLeft => "Paso izquierda",
+            Action::StrafeRight => "Paso derecha",
+            Action::TurnLeft => "Girar izquierda",
+            Action::TurnRight => "Girar derecha",
+            Action::Sprint => "Correr",
+            Action::ToggleMap => "Mapa 2D/3D",
+            Action::Use => "Usar (puertas)",
+        }
+    }
+
+    fn index(self) -> usize {
+        ACTIONS.iter().position(|&a| a == self).unwrap_or(0)
+    }
+}
+
+/// Una entrada física.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyboardKey),
+    Mouse(MouseButton),
+    Pad(GamepadButton),
+    /// Eje de un stick y el signo que activa la acción.
+    Stick(GamepadAxis, i8),
+}
+
+/// Botones del mouse y del gamepad con nombre en el archivo.
+const MOUSE_NAMES: [(&str, MouseButton); 3] = [
+    ("MOUSE_LEFT", MouseButton::MOUSE_BUTTON_LEFT),
+    ("MOUSE_RIGHT", MouseButton::MOUSE_BUTTON_RIGHT),
+    ("MOUSE_MIDDLE", MouseButton::MOUSE_BUTTON_MIDDLE),
+];
+
+const PAD_NAMES: [(&str, GamepadButton); 12] = [
+    ("PAD_UP", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP),
+    ("PAD_DOWN", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+    ("PAD_LEFT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+    ("PAD_RIGHT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+    ("PAD_A", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+    ("PAD_B", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+    ("PAD_X", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+    ("PAD_Y", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP),
+    ("PAD_LB", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1),
+    ("PAD_RB", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+    ("PAD_L3", GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB),
+    ("PAD_SELECT", GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT),
+];
+
+const STICK_NAMES: [(&str, GamepadAxis, i8); 6] = [
+    ("LSTICK_UP", GamepadAxis::GAMEPAD_AXIS_LEFT_Y, -1),
+    ("LSTICK_DOWN", GamepadAxis::GAMEPAD_AXIS_LEFT_Y, 1),
+    ("LSTICK_LEFT", GamepadAxis::GAMEPAD_AXIS_LEFT_X, -1),
+    ("LSTICK_RIGHT", GamepadAxis::GAMEPAD_AXIS_LEFT_X, 1),
+    ("RSTICK_LEFT", GamepadAxis::GAMEPAD_AXIS_RIGHT_X, -1),
+    ("RSTICK_RIGHT", GamepadAxis::GAMEPAD_AXIS_RIGHT_X, 1),
+];
+
+/// Teclas especiales con nombre (las letras y dígitos se nombran por sí mismos).
+const KEY_NAMES: [(&str, KeyboardKey); 14] = [
+    ("SPACE", KeyboardKey::KEY_SPACE),
+    ("ENTER", KeyboardKey::KEY_ENTER),
+    ("TAB", KeyboardKey::KEY_TAB),
+    ("BACKSPACE", KeyboardKey::KEY_BACKSPACE),
+    ("UP", KeyboardKey::KEY_UP),
+    ("DOWN", KeyboardKey::KEY_DOWN),
+    ("LEFT", KeyboardKey::KEY_LEFT),
+    ("RIGHT", KeyboardKey::KEY_RIGHT),
+    ("LEFT_SHIFT", KeyboardKey::KEY_LEFT_SHIFT),
+    ("RIGHT_SHIFT", KeyboardKey::KEY_RIGHT_SHIFT),
+    ("LEFT_CONTROL", KeyboardKey::KEY_LEFT_CONTROL),
+    ("RIGHT_CONTROL", KeyboardKey::KEY_RIGHT_CONTROL),
+    ("LEFT_ALT", KeyboardKey::KEY_LEFT_ALT),
+    ("RIGHT_ALT", KeyboardKey::KEY_RIGHT_ALT),
+];
+
+impl Binding {
+    /// Interpreta un nombre del archivo (`W`, `UP`, `MOUSE_LEFT`, `PAD_A`, `LSTICK_UP`, ...).
+    pub fn parse(name: &str) -> Option<Binding> {
+        let name = name.trim().to_ascii_uppercase();
+        if let Some(&(_, k)) = KEY_NAMES.iter().find(|(n, _)| *n == name) {
+            return Some(Binding::Key(k));
+        }
+        if let Some(&(_, b)) = MOUSE_NAMES.iter().find(|(n, _)| *n == name) {
+            return Some(Binding::Mouse(b));
+        }
+        if let Some(&(_, b)) = PAD_NAMES.iter().find(|(n, _)| *n == name) {
+            return Some(Binding::Pad(b));
+        }
+        if let Some(&(_, axis, sign)) = STICK_NAMES.iter().find(|(n, _, _)| *n == name) {
+            return Some(Binding::Stick(axis, sign));
+        }
+        if let Some(code) = name.strip_prefix("KEY_").and_then(|c| c.parse().ok()) {
+            return key_from_i32(code).map(Binding::Key);
+        }
+        // letras y dígitos: el código de tecla es su ASCII
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphanumeric() => key_from_i32(c as i32).map(Binding::Key),
+            _ => None,
+        }
+    }
+
+    /// Nombre para el archivo y la UI (inverso de `parse`).
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(k) => match KEY_NAMES.iter().find(|(_, key)| *key == k) {
+                Some((n, _)) => n.to_string(),
+                None => {
+                    // sólo dígitos y letras van tal cual; lo demás (';', '=', ...) no se relee
+                    let code = k as i32;
+                    if (0x30..=0x39).contains(&code) || (0x41..=0x5A).contains(&code) {
+                        (code as u8 as char).to_string()
+                    } else {
+                        format!("KEY_{}", code)
+                    }
+                }
+            },
+            Binding::Mouse(b) => MOUSE_NAMES.iter().find(|(_, m)| *m == b).map_or("MOUSE_?", |(n, _)| n).to_string(),
+            Binding::Pad(b) => PAD_NAMES.iter().find(|(_, p)| *p == b).map_or("PAD_?", |(n, _)| n).to_string(),
+            Binding::Stick(axis, sign) => STICK_NAMES
+                .iter()
+                .find(|(_, a, s)| *a == axis && *s == sign)
+                .map_or("STICK_?", |(n, _, _)| n)
+                .to_string(),
+        }
+    }
+
+    /// Entrada del gamepad (botón o stick).
+    fn is_gamepad(self) -> bool {
+        matches!(self, Binding::Pad(_) | Binding::Stick(..))
+    }
+
+    /// Intensidad de la entrada en 0..1 (las teclas y botones son 0 o 1).
+    fn strength(self, rl: &RaylibHandle) -> f32 {
+        let on = |b: bool| if b { 1.0 } else { 0.0 };
+        match self {
+            Binding::Key(k) => on(rl.is_key_down(k)),
+            Binding::Mouse(b) => on(rl.is_mouse_button_down(b)),
+            Binding::Pad(b) => on(rl.is_gamepad_available(0) && rl.is_gamepad_button_down(0, b)),
+            Binding::Stick(axis, sign) => {
+                if !rl.is_gamepad_available(0) { return 0.0; }
+                let v = rl.get_gamepad_axis_movement(0, axis) * sign as f32;
+                if v > STICK_DEADZONE { v.min(1.0) } else { 0.0 }
+            }
+        }
+    }
+
+    fn pressed(self, rl: &RaylibHandle) -> bool {
+        match self {
+            Binding::Key(k) => rl.is_key_pressed(k),
+            Binding::Mouse(b) => rl.is_mouse_button_pressed(b),
+            Binding::Pad(b) => rl.is_gamepad_available(0) && rl.is_gamepad_button_pressed(0, b),
+            Binding::Stick(..) => false, // los sticks no sirven para acciones de un toque
+        }
+    }
+
+    /// Primera entrada que se presionó en este cuadro (para reasignar).
+    pub fn capture(rl: &mut RaylibHandle) -> Option<Binding> {
+        if let Some(k) = rl.get_key_pressed() {
+            return Some(Binding::Key(k));
+        }
+        if let Some(&(_, b)) = MOUSE_NAMES.iter().find(|(_, b)| rl.is_mouse_button_pressed(*b)) {
+            return Some(Binding::Mouse(b));
+        }
+        if rl.is_gamepad_available(0) {
+            if let Some(b) = rl.get_gamepad_button_pressed() {
+                return Some(Binding::Pad(b));
+            }
+            let stick = STICK_NAMES
+                .iter()
+                .find(|(_, axis, sign)| rl.get_gamepad_axis_movement(0, *axis) * *sign as f32 > 0.6);
+            if let Some(&(_, axis, sign)) = stick {
+                return Some(Binding::Stick(axis, sign));
+            }
+        }
+        None
+    }
+}
+
+/// Entradas asignadas a cada acción.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    actions: [Vec<Binding>; ACTIONS.len()],
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut b = Bindings { actions: Default::default() };
+        for action in ACTIONS {
+            b.actions[action.index()] = default_bindings(action);
+        }
+        b
+    }
+}
+
+/// Asignación de fábrica de una acción.
+pub fn default_bindings(action: Action) -> Vec<Binding> {
+    use Binding::*;
+    use KeyboardKey::*;
+    match action {
+        Action::Forward => vec![Key(KEY_W), Key(KEY_UP), Stick(GamepadAxis::GAMEPAD_AXIS_LEFT_Y, -1)],
+        Action::Back => vec![Key(KEY_S), Key(KEY_DOWN), Stick(GamepadAxis::GAMEPAD_AXIS_LEFT_Y, 1)],
+        Action::StrafeLeft => vec![Key(KEY_Q), Stick(GamepadAxis::GAMEPAD_AXIS_LEFT_X, -1)],
+        Action::StrafeRight => vec![Key(KEY_E), Stick(GamepadAxis::GAMEPAD_AXIS_LEFT_X, 1)],
+        Action::TurnLeft => vec![Key(KEY_A), Key(KEY_LEFT), Stick(GamepadAxis::GAMEPAD_AXIS_RIGHT_X, -1)],
+        Action::TurnRight => vec![Key(KEY_D), Key(KEY_RIGHT), Stick(GamepadAxis::GAMEPAD_AXIS_RIGHT_X, 1)],
+        Action::Sprint => vec![Key(KEY_LEFT_SHIFT), Pad(GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB)],
+        Action::ToggleMap => vec![Key(KEY_M), Pad(GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT)],
+        Action::Use => vec![Key(KEY_F), Pad(GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN)],
+    }
+}
+
+impl Bindings {
+    /// Carga las asignaciones; las acciones que el archivo no menciona quedan de fábrica.
+    /// Si el archivo no existe se usan todas las de fábrica.
+    pub fn load(path: &str) -> Result<Self, LoadError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => return Err(LoadError::Io { path: path.to_string(), source }),
+        };
+        let mut bindings = Self::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let bad = |message: String| LoadError::BadHeader { path: path.to_string(), line: n + 1, message };
+            let Some((name, inputs)) = line.split_once('=') else {
+                return Err(bad(format!("se esperaba `accion = entradas`, no {:?}", line)));
+            };
+            let action = ACTIONS
+                .iter()
+                .copied()
+                .find(|a| a.name() == name.trim())
+                .ok_or_else(|| bad(format!("accion desconocida {:?}", name.trim())))?;
+            let list = inputs
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| Binding::parse(s).ok_or_else(|| bad(format!("entrada desconocida {:?}", s.trim()))))
+                .collect::<Result<Vec<_>, _>>()?;
+            bindings.actions[action.index()] = list;
+        }
+        Ok(bindings)
+    }
+
+    /// Guarda todas las asignaciones en el formato que lee `load`.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut out = String::from("# accion = entrada, entrada, ...\n");
+        for action in ACTIONS {
+            out += &format!("{} = {}\n", action.name(), self.describe(action));
+        }
+        fs::write(path, out)
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        &self.actions[action.index()]
+    }
+
+    /// Reasigna una acción: la nueva entrada reemplaza a la primera del mismo tipo
+    /// (teclado/mouse o gamepad) y queda como principal.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let list = &mut self.actions[action.index()];
+        list.retain(|b| *b != binding);
+        if let Some(k) = list.iter().position(|b| b.is_gamepad() == binding.is_gamepad()) {
+            list.remove(k);
+        }
+        list.insert(0, binding);
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.actions[action.index()] = default_bindings(action);
+    }
+
+    /// Entradas de una acción separadas por comas (UI y archivo).
+    pub fn describe(&self, action: Action) -> String {
+        self.get(action).iter().map(|b| b.name()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Intensidad de la acción en 0..1 (la mayor de sus entradas).
+    pub fn strength(&self, rl: &RaylibHandle, action: Action) -> f32 {
+        self.get(action).iter().map(|b| b.strength(rl)).fold(0.0, f32::max)
+    }
+
+    pub fn is_down(&self, rl: &RaylibHandle, action: Action) -> bool {
+        self.strength(rl, action) > 0.0
+    }
+
+    /// La acción se activó en este cuadro.
+    pub fn is_pressed(&self, rl: &RaylibHandle, action: Action) -> bool {
+        self.get(action).iter().any(|b| b.pressed(rl))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_round_trip() {
+        for code in 0..=512 {
+            let Some(key) = key_from_i32(code) else { continue; };
+            let binding = Binding::Key(key);
+            let name = binding.name();
+            assert!(!name.contains('=') && !name.contains(','), "{:?} -> {}", key, name);
+            assert_eq!(Binding::parse(&name), Some(binding), "{:?} -> {}", key, name);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+// level.rs
+
+//! Archivo de nivel: encabezado opcional con metadatos + cuadrícula del laberinto.
+//!
+//! Las líneas del inicio que empiezan con `@` son el encabezado (`@clave: valor`);
+//! el resto del archivo es la cuadrícula que ya leía `load_maze`.
+//! Un `.txt` sin encabezado carga con los valores por defecto.
+//!
+//! ```text
+//! @name: Sector 7
+//! @spawn: 1, 1          (fila, columna de la celda inicial)
+//! @facing: 90           (grados; 0 = +x, 90 = +y)
+//! @wall: | assets/textures/wall_grid7.jpg
+//! @wall: * assets/textures/wall_grid4.jpg   (* = textura por defecto)
+//! @floor: assets/textures/floor3.jpg
+//! @sky: assets/textures/wall_grid.jpg
+//! @music: assets/music/tronMusic.ogg
+//! @movement: cycle      (walk = a pie, cycle = moto de luz)
+//! +--+--+
+//! |    g|
+//! +--+--+
+//! @heights              (opcional: alturas por celda, ver `heights`)
+//! ...2...
+//! ```
+
+use raylib::prelude::Vector2;
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::error::LoadError;
+use crate::heights::{layer_to_text, parse_layer_row, Heights, Layer};
+use crate::player::MoveModel;
+use crate::maze::{is_known_cell, validate, world_to_cell, Diagnostic, Maze};
+
+pub const DEFAULT_WALL_TEXTURE: &str = "assets/textures/wall_grid4.jpg";
+pub const DEFAULT_FLOOR_TEXTURE: &str = "assets/textures/floor3.jpg";
+pub const DEFAULT_SKY_TEXTURE: &str = "assets/textures/wall_grid.jpg";
+pub const DEFAULT_MUSIC: &str = "assets/music/tronMusic.ogg";
+
+/// Texturas por tipo de celda cuando el nivel no dice otra cosa.
+/// Las celdas transparentes usan PNG con alfa.
+pub const DEFAULT_WALL_TEXTURES: [(char, &str); 10] = [
+    ('+', "assets/textures/wall_grid2.jpg"),
+    ('|', "assets/textures/wall_grid7.jpg"),
+    ('-', "assets/textures/wall_grid3.jpg"),
+    ('g', "assets/textures/wall_grid6.jpg"), // meta 'g' también tiene su propia textura
+    ('#', "assets/textures/glass.png"),
+    (':', "assets/textures/fence.png"),
+    ('%', "assets/textures/holo.png"),
+    ('D', "assets/textures/wall_grid.jpg"), // hoja de las puertas
+    ('K', "assets/textures/wall_grid.jpg"), // con cerradura (se tiñen del color de la llave)
+    ('J', "assets/textures/wall_grid.jpg"),
+];
+
+/// Punto de aparición cuando el nivel no define `@spawn` (el spawn fijo de siempre).
+const DEFAULT_SPAWN: Vector2 = Vector2::new(190.0, 190.0);
+
+/// Un nivel listo para jugar.
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub maze: Maze,
+    pub spawn: Option<(usize, usize)>, // (fila, columna)
+    pub facing: f32,                   // radianes
+    pub wall_default: String,
+    pub wall_textures: Vec<(char, String)>,
+    pub floor_texture: String,
+    pub sky_texture: String,
+    pub music: String,
+    pub movement: MoveModel,
+    pub heights: Heights,
+}
+
+impl Level {
+    /// Nivel con los valores por defecto para una cuadrícula dada.
+    pub fn with_defaults(name: &str, maze: Maze) -> Self {
+        Level {
+            name: name.to_string(),
+            maze,
+            spawn: None,
+            facing: PI / 3.0,
+            wall_default: DEFAULT_WALL_TEXTURE.to_string(),
+            wall_textures: DEFAULT_WALL_TEXTURES
+                .iter()
+                .map(|&(c, p)| (c, p.to_string()))
+                .collect(),
+            floor_texture: DEFAULT_FLOOR_TEXTURE.to_string(),
+            sky_texture: DEFAULT_SKY_TEXTURE.to_string(),
+            music: DEFAULT_MUSIC.to_string(),
+            movement: MoveModel::Walk,
+            heights: Heights::default(),
+        }
+    }
+
+    /// Posición inicial en coordenadas de mundo (centro de la celda de `@spawn`).
+    pub fn spawn_position(&self, block_size: usize) -> Vector2 {
+        match self.spawn {
+            Some((i, j)) => {
+                let bs = block_size as f32;
+                Vector2::new((j as f32 + 0.5) * bs, (i as f32 + 0.5) * bs)
+            }
+            None => DEFAULT_SPAWN,
+        }
+    }
+
+    /// Celda (fila, columna) donde aparece el jugador.
+    pub fn spawn_cell(&self, block_size: usize) -> (usize, usize) {
+        let pos = self.spawn_position(block_size);
+        world_to_cell(pos.x, pos.y, block_size)
+    }
+
+    /// Revisa el mapa del nivel (ver `maze::validate`); acepta las celdas declaradas con `@wall`.
+    pub fn validate(&self, block_size: usize) -> Vec<Diagnostic> {
+        let declared: Vec<char> = self.wall_textures.iter().map(|(c, _)| *c).collect();
+        validate(&self.maze, self.spawn_cell(block_size), &declared)
+    }
+
+    /// Asigna (o reemplaza) la textura de un tipo de celda.
+    fn set_wall_texture(&mut self, ch: char, path: &str) {
+        if ch == '*' {
+            self.wall_default = path.to_string();
+            return;
+        }
+        match self.wall_textures.iter_mut().find(|(c, _)| *c == ch) {
+            Some(entry) => entry.1 = path.to_string(),
+            None => self.wall_textures.push((ch, path.to_string())),
+        }
+    }
+}
+
+/// Carga un nivel desde disco. El nombre por defecto es el nombre del archivo.
+pub fn load_level(filename: &str) -> Result<Level, LoadError> {
+    let text = fs::read_to_string(filename).map_err(|source| LoadError::Io {
+        path: filename.to_string(),
+        source,
+    })?;
+    let stem = Path::new(filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(filename);
+    parse_level(filename, stem, &text)
+}
+
+/// Interpreta el texto de un nivel: encabezado `@clave: valor` y luego la cuadrícula.
+/// `path` sólo se usa para los mensajes de error.
+/// La cuadrícula sólo puede tener celdas conocidas o las que declare un `@wall`.
+/// Después de la cuadrícula pueden venir las secciones `@heights` y `@floors`.
+pub fn parse_level(path: &str, default_name: &str, text: &str) -> Result<Level, LoadError> {
+    let mut lines = text.lines().enumerate().peekable();
+    let mut header = Vec::new();
+    while let Some(line) = lines.next_if(|(_, l)| l.starts_with('@')) {
+        header.push(line);
+    }
+    let mut grid: Vec<(usize, &str)> = lines.collect();
+    let sections = grid
+        .iter()
+        .position(|(_, l)| is_section(l))
+        .map_or_else(Vec::new, |k| grid.split_off(k));
+
+    let mut level = Level::with_defaults(default_name, Vec::new());
+    let bad_header = |n: usize, message: String| LoadError::BadHeader {
+        path: path.to_string(),
+        line: n + 1,
+        message,
+    };
+
+    for (n, line) in header {
+        let Some((key, value)) = line[1..].split_once(':') else {
+            return Err(bad_header(n, format!("se esperaba `@clave: valor`, no {:?}", line)));
+        };
+        let value = value.trim();
+        match key.trim() {
+            "name" => level.name = value.to_string(),
+            "spawn" => {
+                let cell = parse_cell(value)
+                    .ok_or_else(|| bad_header(n, format!("@spawn espera \"fila, columna\", no {:?}", value)))?;
+                level.spawn = Some(cell);
+            }
+            "facing" => {
+                let deg: f32 = value
+                    .parse()
+                    .map_err(|_| bad_header(n, format!("@facing espera grados, no {:?}", value)))?;
+                level.facing = deg.to_radians();
+            }
+            "wall" => {
+                let mut chars = value.chars();
+                let ch = chars.next();
+                let tex = chars.as_str().trim();
+                match ch {
+                    Some(ch) if !tex.is_empty() => level.set_wall_texture(ch, tex),
+                    _ => return Err(bad_header(n, format!("@wall espera \"<celda> <textura>\", no {:?}", value))),
+                }
+            }
+            "floor" => level.floor_texture = value.to_string(),
+            "sky" => level.sky_texture = value.to_string(),
+            "music" => level.music = value.to_string(),
+            "movement" => {
+                level.movement = MoveModel::parse(value)
+                    .ok_or_else(|| bad_header(n, format!("@movement espera walk o cycle, no {:?}", value)))?;
+            }
+            other => return Err(bad_header(n, format!("clave desconocida @{}", other))),
+        }
+    }
+
+    for &(n, line) in &grid {
+        for (col, ch) in line.chars().enumerate() {
+            let declared = level.wall_textures.iter().any(|(c, _)| *c == ch);
+            if !is_known_cell(ch) && !declared {
+                return Err(LoadError::BadChar { path: path.to_string(), line: n + 1, column: col + 1, ch });
+            }
+        }
+    }
+
+    let mut layer: Option<&mut Layer> = None;
+    for (n, line) in sections {
+        match line.trim_end() {
+            "@heights" => layer = Some(&mut level.heights.walls),
+            "@floors" => layer = Some(&mut level.heights.floors),
+            _ => {
+                let row = parse_layer_row(line).map_err(|col| LoadError::BadChar {
+                    path: path.to_string(),
+                    line: n + 1,
+                    column: col + 1,
+                    ch: line.chars().nth(col).unwrap_or(' '),
+                })?;
+                // `sections` empieza siempre con un encabezado de sección
+                if let Some(layer) = layer.as_mut() {
+                    layer.push(row);
+                }
+            }
+        }
+    }
+
+    level.maze = grid.iter().map(|(_, line)| line.chars().collect()).collect();
+    if level.maze.iter().all(|row| row.is_empty()) {
+        return Err(LoadError::Empty { path: path.to_string() });
+    }
+    Ok(level)
+}
+
+/// Texto del nivel en el formato que lee `parse_level`.
+/// Sólo se escriben las claves que difieren de los valores por defecto (más `@name`).
+pub fn level_to_text(level: &Level) -> String {
+    let defaults = Level::with_defaults(&level.name, Vec::new());
+    let mut out = format!("@name: {}\n", level.name);
+    if let Some((i, j)) = level.spawn {
+        out += &format!("@spawn: {}, {}\n", i, j);
+    }
+    if (level.facing - defaults.facing).abs() > 1e-4 {
+        out += &format!("@facing: {}\n", level.facing.to_degrees().round());
+    }
+    if level.wall_default != defaults.wall_default {
+        out += &format!("@wall: * {}\n", level.wall_default);
+    }
+    for (ch, path) in &level.wall_textures {
+        if !defaults.wall_textures.contains(&(*ch, path.clone())) {
+            out += &format!("@wall: {} {}\n", ch, path);
+        }
+    }
+    for (key, value, default) in [
+        ("floor", &level.floor_texture, &defaults.floor_texture),
+        ("sky", &level.sky_texture, &defaults.sky_texture),
+        ("music", &level.music, &defaults.music),
+    ] {
+        if value != default {
+            out += &format!("@{}: {}\n", key, value);
+        }
+    }
+    if level.movement != defaults.movement {
+        out += &format!("@movement: {}\n", level.movement.name());
+    }
+    for row in &level.maze {
+        out.extend(row.iter());
+        out.push('\n');
+    }
+    for (key, layer) in [("heights", &level.heights.walls), ("floors", &level.heights.floors)] {
+        if layer.iter().flatten().any(Option::is_some) {
+            out += &format!("@{}\n{}", key, layer_to_text(layer));
+        }
+    }
+    out
+}
+
+/// Guarda el nivel en disco (ver `level_to_text`).
+pub fn save_level(filename: &str, level: &Level) -> io::Result<()> {
+    fs::write(filename, level_to_text(level))
+}
+
+/// Línea que abre una sección de alturas después de la cuadrícula.
+fn is_section(line: &str) -> bool {
+    matches!(line.trim_end(), "@heights" | "@floors")
+}
+
+/// "fila, columna" -> (fila, columna)
+fn parse_cell(value: &str) -> Option<(usize, usize)> {
+    let (i, j) = value.split_once(',')?;
+    Some((i.trim().parse().ok()?, j.trim().parse().ok()?))
+}
@@ -10,7 +10,7 @@
 /// Estados principales del juego.
 #[derive(Copy, Debug, PartialEq, Eq)]
 #[derive(Clone)]
-enum GameState { Title, LevelSelect, Playing, Win }
+enum GameState { Title, LevelSelect, Playing, Win, Crashed, Editor, Controls }
 
 /// Texturas para las pantallas inicial y de victoria.
 struct Assets {
@@ -25,14 +25,39 @@ mod maze;
 mod caster;
 mod player;
 mod sprite;
+mod projection;
+mod export;
+mod level;
+mod error;
+mod generator;
+mod pathfind;
+mod editor;
+mod input;
+mod trail;
+mod ai;
+mod heights;
+mod doors;
+mod pickup;
 
 use line::line;
 use maze::{Maze,load_maze};
-use caster::{cast_ray, Intersect};
+use level::{Level, load_level};
+use error::LoadError;
+use generator::{generate, MazeConfig};
+use pathfind::path_to_goal;
+use editor::Editor;
+use input::{Action, Binding, Bindings, ACTIONS, BINDINGS_FILE};
+use trail::{TrailWriter, TRAIL_CELL};
+use ai::AiController;
+use caster::{cast_ray, trace_ray, walk_ray, RayTrace, Intersect, HitKind};
+use heights::Heights;
+use doors::{is_closed_door, lock_key, Doors, DOOR_CELL, OPEN_DOOR_CELL};
+use pickup::{take_pickups, Inventory, PickupKind, PICKUP_RADIUS};
 use framebuffer::Framebuffer;
-use player::{Player, process_events};
+use player::{Player, MoveModel, Blocked, process_events, EYE_HEIGHT};
 use raylib::audio::{RaylibAudio, Music, Sound};
-use sprite::{Sprite, load_frames, render_sprites};
+use sprite::{Sprite, DepthColumn, GlassLayer, load_frames, render_sprites};
+use projection::Projection;
 
 use raylib::prelude::*;
 use std::thread;
@@ -40,7 +65,7 @@ use std::time::Duration;
 use std::f32::consts::PI;
 use std::collections::HashMap;
 
-use crate::maze::world_to_cell;
+use crate::maze::{world_to_cell, cell_at, maze_width, take_cells, MOTO_CELL};
 
 /// Imagen en CPU para texturizar paredes, piso y cielo.
 #[derive(Clone)]
@@ -52,9 +77,12 @@ pub struct CpuImage {
 
 impl CpuImage {
     /// Carga una imagen desde disco y la convierte a formato CpuImage.
-    pub fn from_path(path: &str) -> Self {
+    pub fn from_path(path: &str) -> Result<Self, LoadError> {
         // Carga
-        let img = Image::load_image(path).expect("No pude cargar la imagen de pared");
+        let img = Image::load_image(path).map_err(|e| LoadError::Image {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })?;
         let w = img.width as usize;
         let h = img.height as usize;
 
@@ -62,7 +90,7 @@ impl CpuImage {
         let colors = img.get_image_data();              
         let pixels: Vec<Color> = colors.to_vec();
 
-        Self { w, h, pixels }
+        Ok(Self { w, h, pixels })
     }
 
     /// Muestra un color de la imagen usando coordenadas normalizadas (u,v).
@@ -109,11 +137,28 @@ fn scale_color(c: Color, f: f32) -> Color {
     Color::new(fr, fg, fb, c.a)
 }
 
+/// Pone `over` encima de `under` según el alfa de `over`.
+fn blend(under: Color, over: Color) -> Color {
+    let a = over.a as f32 / 255.0;
+    let mix = |u: u8, o: u8| (u as f32 * (1.0 - a) + o as f32 * a) as u8;
+    Color::new(mix(under.r, over.r), mix(under.g, over.g), mix(under.b, over.b), 255)
+}
+
+/// Color de las estelas de moto (brillan igual de cerca y de lejos).
+const TRAIL_COLOR: Color = Color::new(255, 230, 60, 255);
+
 /// Devuelve el color neón para cada tipo de pared en el minimapa.
 fn tron_wall_color(cell: char) -> Color {
+    if let Some(key) = lock_key(cell) {
+        return PickupKind::Key(key).color(); // puerta con cerradura: color de su llave
+    }
     match cell {
         '+' | '|' | '-' => Color::new(0, 255, 255, 255),     // cian neón
         'g'             => Color::new(255, 140, 0, 255),     // naranja meta
+        TRAIL_CELL      => TRAIL_COLOR,                      // estela
+        DOOR_CELL       => Color::new(0, 255, 140, 255),     // puerta
+        OPEN_DOOR_CELL  => Color::new(0, 90, 60, 255),       // puerta abierta
+        '#' | ':' | '%' => Color::new(200, 90, 255, 255),    // vidrio / barreras
         _               => Color::new(180, 180, 255, 255),   // fallback
     }
 }
@@ -130,9 +175,17 @@ fn draw_scanlines(d: &mut RaylibDrawHandle, w: i32, h: i32, spacing: i32, alpha:
 
 /// Color de cada celda en el render 2D.
 fn cell_to_color(cell: char) -> Color {
+    if let Some(kind) = lock_key(cell).map(PickupKind::Key).or(PickupKind::from_cell(cell)) {
+        return kind.color(); // cerraduras y objetos (en el editor)
+    }
     match cell {
         '+' | '|' | '-' => Color::new(0, 210, 255, 255),  // cian más suave (no tan chillón)
         'g'             => Color::new(255, 130, 20, 255), // naranja un poco más cálido
+        TRAIL_CELL      => TRAIL_COLOR,
+        DOOR_CELL       => Color::new(0, 220, 130, 255),
+        OPEN_DOOR_CELL  => Color::new(0, 80, 55, 255),
+        '#' | ':' | '%' => Color::new(150, 70, 200, 255), // vidrio / barreras
+        MOTO_CELL       => Color::new(230, 40, 40, 255),  // moto enemiga (en el editor)
         _               => Color::new(16, 20, 32, 255),   // fallback oscuro (poco probable)
     }
 }
@@ -169,7 +222,7 @@ fn render_minimap(
     origin_y: usize,
     scale: usize,
 ) {
-    let map_w = maze[0].len() * scale;
+    let map_w = maze_width(maze) * scale;
     let map_h = maze.len() * scale;
     framebuffer.set_current_color(Color::new(8, 10, 18, 255));
     for x in origin_x..origin_x + map_w {
@@ -216,10 +269,45 @@ fn render_minimap(
     }
 }
 
+/// Origen y escala para que el mapa completo quepa centrado en el framebuffer
+/// (vista 2D y editor).
+fn full_map_layout(framebuffer: &Framebuffer, maze: &Maze) -> (usize, usize, usize) {
+    let maze_w = maze_width(maze).max(1);
+    let maze_h = maze.len().max(1);
+    let fb_w = framebuffer.width as usize;
+    let fb_h = framebuffer.height as usize;
+    let scale = (fb_w / maze_w).min(fb_h / maze_h);
+    ((fb_w - maze_w * scale) / 2, (fb_h - maze_h * scale) / 2, scale)
+}
+
+/// Celda del mapa bajo un punto del framebuffer, con el layout de `full_map_layout`.
+fn map_cell_at(maze: &Maze, layout: (usize, usize, usize), x: u32, y: u32) -> Option<(usize, usize)> {
+    let (ox, oy, scale) = layout;
+    let (x, y) = (x as usize, y as usize);
+    if scale == 0 || x < ox || y < oy { return None; }
+    let (i, j) = ((y - oy) / scale, (x - ox) / scale);
+    if i < maze.len() && j < maze_width(maze) { Some((i, j)) } else { None }
+}
+
+/// Marco de una celda del mapa (cursor del editor).
+fn draw_cell_outline(framebuffer: &mut Framebuffer, layout: (usize, usize, usize), cell: (usize, usize), color: Color) {
+    let (ox, oy, scale) = layout;
+    let (x0, y0) = ((ox + cell.1 * scale) as u32, (oy + cell.0 * scale) as u32);
+    let s = scale.max(1) as u32;
+    framebuffer.set_current_color(color);
+    for k in 0..s {
+        framebuffer.set_pixel(x0 + k, y0);
+        framebuffer.set_pixel(x0 + k, y0 + s - 1);
+        framebuffer.set_pixel(x0, y0 + k);
+        framebuffer.set_pixel(x0 + s - 1, y0 + k);
+    }
+}
+
 /// Renderiza el mundo en modo 2D (vista cenital).
 pub fn render_maze(
   framebuffer: &mut Framebuffer,
   maze: &Maze,
+  doors: &Doors,
   block_size: usize,
   player: &Player,
 ) {
@@ -231,161 +319,445 @@ pub fn render_maze(
     }
   }
 
-  framebuffer.set_current_color(Color::WHITESMOKE);
-
   // dibujar lo que el jugador ve
   let num_rays = 5;
   for i in 0..num_rays {
     let current_ray = i as f32 / num_rays as f32; // current ray divided by total rays
     let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
-    cast_ray(framebuffer, &maze, &player, a, block_size, true);
+    let trace = trace_ray(maze, doors, player.pos, a, block_size, MAX_RAY_DISTANCE);
+    draw_ray_debug(framebuffer, player, &trace, block_size);
   }
 }
 
-/// Renderiza el mundo en modo 3D usando raycasting.
-/// Incluye texturizado de paredes, piso y cielo.
-fn render_world(
-    framebuffer: &mut Framebuffer,
-    maze: &Maze,
+/// Dibuja un rayo en la vista 2D: las celdas que recorrió y la línea hasta el impacto.
+fn draw_ray_debug(
+  framebuffer: &mut Framebuffer,
+  player: &Player,
+  trace: &RayTrace,
+  block_size: usize,
+) {
+  // centro de cada celda visitada
+  framebuffer.set_current_color(Color::new(60, 70, 110, 255));
+  for &(i, j) in &trace.cells {
+    let cx = (j * block_size + block_size / 2) as u32;
+    let cy = (i * block_size + block_size / 2) as u32;
+    framebuffer.set_pixel(cx, cy);
+  }
+
+  framebuffer.set_current_color(Color::WHITESMOKE);
+  line(framebuffer, player.pos, trace.hit.hit);
+}
+
+/// Distancia máxima (en unidades de mundo) que recorre un rayo antes de rendirse.
+const MAX_RAY_DISTANCE: f32 = 150.0 * 48.0;
+
+/// Color del vacío fuera del mapa (bordes abiertos).
+const VOID_COLOR: Color = Color::new(4, 6, 12, 255);
+
+/// Opciones del render 3D.
+#[derive(Clone, Copy, Debug)]
+struct RenderSettings {
+    projection: Projection,
+    max_distance: f32, // distancia máxima de los rayos (unidades de mundo)
+    threads: usize,    // hilos para pintar el framebuffer (1 = sin hilos)
+}
+
+impl RenderSettings {
+    fn new(threads: usize) -> Self {
+        Self { projection: Projection::Perspective, max_distance: MAX_RAY_DISTANCE, threads: threads.max(1) }
+    }
+}
+
+/// Hilos por defecto: los núcleos disponibles.
+fn default_render_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Cara vertical de una celda que golpeó el rayo.
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    cell: char,
+    u: f32,           // coordenada horizontal de textura
+    y_top: f32,       // fila (sin recortar) de lo más alto de la celda
+    px_per_unit: f32, // filas que mide una altura de pared a esta distancia
+    wall_h: f32,      // alto de la pared (la textura se estira sobre esto)
+    gain: f32,        // brillo de la pared en esta columna
+}
+
+/// Tramo de filas de una columna: la cara vertical de una celda o la superficie de arriba.
+#[derive(Clone, Copy, Debug)]
+enum SpanKind {
+    Face(Face),
+    /// Superficie horizontal (piso elevado o tope de una pared baja) a altura `height`.
+    Top { cell: char, height: f32 },
+}
+
+/// Filas `[y0, y1)` de una columna que pinta un mismo `SpanKind`.
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    y0: i32,
+    y1: i32,
+    kind: SpanKind,
+}
+
+/// Lo que el raycaster encontró para una columna de pantalla.
+/// Se calcula para todas las columnas antes de pintar.
+/// Las filas que no cubre ningún tramo son cielo (arriba del horizonte) o piso a altura 0.
+struct ColumnInfo {
+    floor_dir: Vector2, // dirección para piso/cielo (según la proyección)
+    kind: HitKind,      // cómo terminó el rayo
+    dist: f32,          // profundidad donde terminó el rayo
+    spans: Vec<Span>,   // de cerca a lejos (de abajo hacia arriba en pantalla)
+    depth: DepthColumn, // qué tapa la columna y sus caras transparentes (se pintan encima)
+}
+
+/// Datos de solo lectura que comparten las franjas al pintar.
+struct WorldView<'a> {
+    player: &'a Player,
+    doors: &'a Doors,
     block_size: usize,
-    player: &Player,
-    walls: &WallTex,          
-    floor_tex: &CpuImage,
-    sky_tex: &CpuImage,
+    walls: &'a WallTex,
+    floor_tex: &'a CpuImage,
+    sky_tex: &'a CpuImage,
+    hh: f32,      // media altura de la pantalla (escala de la proyección)
+    horizon: f32, // fila del horizonte (se mueve con `player.pitch`)
+    eye: f32,     // altura de los ojos (`player.eye()`)
+    dpp: f32,
+}
+
+impl WorldView<'_> {
+    /// Filas de pantalla que mide una altura de pared a profundidad `dist`.
+    fn px_per_unit(&self, dist: f32) -> f32 {
+        self.hh * self.dpp / dist
+    }
+
+    /// Fila de pantalla (sin recortar) de la altura `z` a profundidad `dist`.
+    fn row_of(&self, z: f32, dist: f32) -> f32 {
+        self.horizon + (self.eye - z) * self.px_per_unit(dist)
+    }
+
+    /// Profundidad del punto de una superficie horizontal a altura `z` que cae en la fila `y`
+    /// (inverso de `row_of`). `None` si desde los ojos no se ve en esa fila.
+    fn surface_dist(&self, z: f32, y: i32) -> Option<f32> {
+        let denom = y as f32 - self.horizon;
+        if denom < 0.0001 { return None; }
+        let dist = (self.eye - z) * self.hh * self.dpp / denom;
+        (dist > 0.0).then_some(dist)
+    }
+}
+
+/// Recorre el rayo de la columna `i` y arma sus tramos de cerca a lejos.
+/// Cada celda es una columna sólida de alto `heights.top`: se dibuja su cara si asoma
+/// sobre lo anterior y su parte de arriba si queda bajo los ojos. `clip` es la primera fila
+/// ya cubierta; todo lo que viene detrás sólo puede verse por encima de ella.
+#[allow(clippy::too_many_arguments)]
+fn cast_column(
+    view: &WorldView,
+    maze: &Maze,
+    heights: &Heights,
+    max_top: f32,
+    settings: &RenderSettings,
     tron_time: f32,
-    depth: &mut [f32],
-) {
-    let w = framebuffer.width as i32;
-    let h = framebuffer.height as i32;
-    let hh = h as f32 * 0.5;
+    i: i32,
+    w: i32,
+    h: i32,
+) -> ColumnInfo {
+    let player = view.player;
+
+    // Rayo de esta columna según la proyección
+    let ray = settings.projection.column_ray(player, i, w);
+    let walk = walk_ray(maze, view.doors, player.pos, ray.angle, view.block_size, settings.max_distance);
+
+    // Sombreado suave tipo TRON
+    let pulse = (tron_time * 3.0).sin() * 0.06;
+    let shade = |dist: f32| {
+        let dist_falloff = (1.15 / (1.0 + dist * 0.025)).clamp(0.22, 1.0);
+        (dist_falloff + pulse).clamp(0.18, 1.0)
+    };
 
-    for i in 0..w {
-        // Ángulo del rayo para esta columna
-        let t = i as f32 / w as f32;
-        let a = player.a - (player.fov * 0.5) + (player.fov * t);
-        let dir = Vector2::new(a.cos(), a.sin());
+    let mut spans = Vec::new();
+    let mut glass = Vec::new();
+    let mut occluders = Vec::new();
+    let mut clip = h;
+    // celda en la que va el rayo: (carácter, altura del tope, profundidad a la que entró)
+    // (la del jugador cuenta sólo con su piso: aunque sea la meta, está adentro)
+    let mut cur = (' ', walk.start_cell().map_or(0.0, |(i, j)| heights.floor(i, j)), 0.0005f32);
 
-        // Raycast
-        let intersect = cast_ray(framebuffer, maze, player, a, block_size, false);
+    for step in walk {
 
         // --- Parche anti-freeze ---
-        let mut dist = intersect.distance;
+        let mut dist = ray.depth(step.distance);
         if !dist.is_finite() { dist = 1.0; }
         if dist < 0.0005 { dist = 0.0005; }
 
-        // Guarda la distancia del muro para esta columna:
-        if let Some(slot) = depth.get_mut(i as usize) {
-            *slot = dist;
+        // Tope de la celda que termina aquí (sólo se ve si está bajo los ojos)
+        let (cell, top, entered) = cur;
+        if top < view.eye {
+            let far = view.row_of(top, dist).max(0.0) as i32;
+            let near = (view.row_of(top, entered) as i32).min(clip);
+            if far < near {
+                if top > 0.0 || cell != ' ' {
+                    spans.push(Span { y0: far, y1: near, kind: SpanKind::Top { cell, height: top } });
+                }
+                clip = far;
+                occluders.push((dist, clip));
+            }
         }
 
-        // Proyección de pared
-        let dpp = 70.0;
-        let stake_h = (hh / dist) * dpp;
-        let wall_top = (hh - stake_h * 0.5) as i32;
-        let wall_bot = (hh + stake_h * 0.5) as i32;
+        if matches!(step.kind, HitKind::NoHit | HitKind::OutOfBounds) {
+            let depth = DepthColumn { occluders, glass };
+            return ColumnInfo { floor_dir: ray.floor_dir, kind: step.kind, dist, spans, depth };
+        }
 
-        let start = wall_top.clamp(0, h);
-        let end   = wall_bot.clamp(0, h);
+        // Celda transparente: se anota su cara y el rayo sigue (no tapa nada)
+        let (ci, cj) = step.cell;
+        if step.kind == HitKind::Transparent {
+            let (floor, wall_h) = (heights.floor(ci, cj), heights.wall(ci, cj));
+            let y_top = view.row_of(floor + wall_h, dist);
+            let y0 = y_top.max(0.0) as i32;
+            let y1 = (view.row_of(floor, dist) as i32).min(clip);
+            if y0 < y1 {
+                let face = Face {
+                    cell: step.impact,
+                    u: step.texture_u(view.block_size),
+                    y_top,
+                    px_per_unit: view.px_per_unit(dist),
+                    wall_h,
+                    gain: shade(dist),
+                };
+                // se sombrea aquí: lo mezclan encima tanto la columna como los sprites de atrás
+                let colors = (y0..y1).map(|y| shade_wall(view, &face, y)).collect();
+                glass.push(GlassLayer { dist, y0, colors });
+            }
+        }
 
-        // ---------------------------
-        //  A) CIELO / FONDO (0..start)
-        // ---------------------------
-        if start > 0 {
-            for y in 0..start {
-                let denom = hh - y as f32;
-                if denom.abs() < 0.0001 { continue; }
-                let row_dist = (hh / denom) * dpp;
+        // Cara de la celda nueva si sobresale de la anterior
+        // (la hoja de una puerta es una pared completa en medio de su celda)
+        let next_top = if step.kind == HitKind::Wall && is_closed_door(step.impact) {
+            heights.floor(ci, cj) + heights.wall(ci, cj)
+        } else {
+            heights.top(maze, ci, cj)
+        };
+        if next_top > top {
+            let y_top = view.row_of(next_top, dist);
+            let y0 = y_top.max(0.0) as i32;
+            let y1 = (view.row_of(top, dist) as i32).min(clip);
+            if y0 < y1 {
+                let kind = SpanKind::Face(Face {
+                    cell: step.impact,
+                    // u de textura según la cara que golpeó el DDA
+                    u: step.texture_u(view.block_size),
+                    y_top,
+                    px_per_unit: view.px_per_unit(dist),
+                    wall_h: if step.kind == HitKind::Wall { heights.wall(ci, cj) } else { next_top },
+                    gain: shade(dist),
+                });
+                spans.push(Span { y0, y1, kind });
+                clip = y0;
+                occluders.push((dist, clip));
+            }
+        }
 
-                let wx = player.pos.x + dir.x * row_dist;
-                let wy = player.pos.y + dir.y * row_dist;
+        // Ya no queda nada visible detrás: pantalla cubierta o nada más alto que esto
+        if clip <= 0 || (next_top >= max_top && next_top >= view.eye) {
+            let depth = DepthColumn { occluders, glass };
+            return ColumnInfo { floor_dir: ray.floor_dir, kind: HitKind::Wall, dist, spans, depth };
+        }
+        cur = (step.impact, next_top, dist);
+    }
+    let depth = DepthColumn { occluders, glass };
+    ColumnInfo { floor_dir: ray.floor_dir, kind: HitKind::NoHit, dist: f32::INFINITY, spans, depth }
+}
 
-                let u = ((wx / block_size as f32).fract() + 1.0).fract();
-                let v = ((wy / block_size as f32).fract() + 1.0).fract();
+/// Color del cielo en la fila `y` (arriba de la pared).
+fn shade_sky(view: &WorldView, col: &ColumnInfo, y: i32) -> Option<Color> {
+    let hh = view.hh;
+    let denom = view.horizon - y as f32;
+    if denom.abs() < 0.0001 { return None; }
+    let row_dist = (hh / denom) * view.dpp;
 
-                let mut col = sky_tex.sample_repeat(u, v);
-                let sky_gain = (0.65 + (y as f32 / hh) * 0.2).clamp(0.5, 0.95);
-                col = scale_color(col, sky_gain);
+    let wx = view.player.pos.x + col.floor_dir.x * row_dist;
+    let wy = view.player.pos.y + col.floor_dir.y * row_dist;
 
-                framebuffer.set_current_color(col);
-                framebuffer.set_pixel(i as u32, y as u32);
-            }
-        }
+    let u = ((wx / view.block_size as f32).fract() + 1.0).fract();
+    let v = ((wy / view.block_size as f32).fract() + 1.0).fract();
 
-        // ---------------------------------
-        //  B) PARED (start..end) con textura
-        // ---------------------------------
-        if end > start {
-            // Punto de impacto para u/v en pared
-            let hit_x = player.pos.x + dist * dir.x;
-            let hit_y = player.pos.y + dist * dir.y;
-
-            let fx = ((hit_x / block_size as f32).fract() + 1.0).fract();
-            let fy = ((hit_y / block_size as f32).fract() + 1.0).fract();
-
-            // ¿vertical u horizontal?
-            let edge_x = fx.min(1.0 - fx);
-            let edge_y = fy.min(1.0 - fy);
-            let u_wall = if edge_x < edge_y { fy } else { fx };
-
-            // Celda golpeada (elige textura)
-            let (ci, cj) = world_to_cell(hit_x, hit_y, block_size);
-            let cell_ch = if ci < maze.len() && cj < maze[ci].len() { maze[ci][cj] } else { ' ' };
-            let wall_img = walls.for_cell(cell_ch);
-
-            // Sombreado suave tipo TRON
-            let pulse = (tron_time * 3.0).sin() * 0.06;
-            let dist_falloff = (1.15 / (1.0 + dist * 0.025)).clamp(0.22, 1.0);
-            let column_gain = (dist_falloff + pulse).clamp(0.18, 1.0);
-
-            let denom = (end - start).max(1) as f32;
-            for y in start..end {
-                let v_wall = (y - start) as f32 / denom; // 0..1
-                let mut col = wall_img.sample_repeat(u_wall, v_wall);
-
-                // (Opcional) si quieres teñir la meta 'g' aunque tenga su propia textura, deja esto:
-                if cell_ch == 'g' {
-                    let tint = Color::new(255, 140, 0, 255);
-                    col = Color::new(
-                        (col.r as f32 * 0.4 + tint.r as f32 * 0.6) as u8,
-                        (col.g as f32 * 0.4 + tint.g as f32 * 0.6) as u8,
-                        (col.b as f32 * 0.4 + tint.b as f32 * 0.6) as u8,
-                        255
-                    );
-                }
+    let c = view.sky_tex.sample_repeat(u, v);
+    let sky_gain = (0.65 + (y as f32 / view.horizon.max(1.0)) * 0.2).clamp(0.5, 0.95);
+    Some(scale_color(c, sky_gain))
+}
 
-                col = scale_color(col, column_gain);
-                framebuffer.set_current_color(col);
-                framebuffer.set_pixel(i as u32, y as u32);
-            }
-        }
+/// Color de una cara vertical en la fila `y`.
+fn shade_wall(view: &WorldView, face: &Face, y: i32) -> Color {
+    let Face { cell, u, gain, .. } = *face;
+    // v = 0 arriba de la pared y 1 abajo; lo que quede más abajo (un piso elevado) repite la textura
+    let v_wall = (y as f32 - face.y_top) / (face.px_per_unit * face.wall_h.max(0.01));
 
-        // ---------------------------
-        //  C) PISO (end..h)
-        // ---------------------------
-        if end < h {
-            for y in end..h {
-                let denom = y as f32 - hh;
-                if denom.abs() < 0.0001 { continue; }
-                let row_dist = (hh / denom) * dpp;
+    // Estela: franja de luz sin textura, más intensa al centro y casi sin atenuar por distancia
+    if cell == TRAIL_CELL {
+        let core = 1.0 - (v_wall.clamp(0.0, 1.0) - 0.5).abs() * 2.0; // 0 en los bordes, 1 al centro
+        return scale_color(TRAIL_COLOR, (0.55 + core * 0.6).min(1.0) * gain.max(0.75));
+    }
 
-                let wx = player.pos.x + dir.x * row_dist;
-                let wy = player.pos.y + dir.y * row_dist;
+    // escalones de piso elevado: con la textura del piso
+    let wall_img = if cell == ' ' { view.floor_tex } else { view.walls.for_cell(cell) };
+    let mut c = wall_img.sample_repeat(u, v_wall);
 
-                let u = ((wx / block_size as f32).fract() + 1.0).fract();
-                let v = ((wy / block_size as f32).fract() + 1.0).fract();
+    // (Opcional) si quieres teñir la meta 'g' aunque tenga su propia textura, deja esto:
+    // las puertas con cerradura se tiñen del color de su llave
+    let tint = match cell {
+        'g' => Some(Color::new(255, 140, 0, 255)),
+        _ => lock_key(cell).map(|key| PickupKind::Key(key).color()),
+    };
+    if let Some(tint) = tint {
+        c = Color::new(
+            (c.r as f32 * 0.4 + tint.r as f32 * 0.6) as u8,
+            (c.g as f32 * 0.4 + tint.g as f32 * 0.6) as u8,
+            (c.b as f32 * 0.4 + tint.b as f32 * 0.6) as u8,
+            255
+        );
+    }
 
-                let mut col = floor_tex.sample_repeat(u, v);
+    scale_color(c, gain)
+}
 
-                let floor_gain = (0.95 / (1.0 + row_dist * 0.01)).clamp(0.25, 0.9);
-                col = scale_color(col, floor_gain);
+/// Color de la superficie de arriba de una celda (a altura `height`) en la fila `y`.
+fn shade_top(view: &WorldView, col: &ColumnInfo, cell: char, height: f32, y: i32) -> Option<Color> {
+    let dist = view.surface_dist(height, y)?;
 
-                framebuffer.set_current_color(col);
-                framebuffer.set_pixel(i as u32, y as u32);
+    let wx = view.player.pos.x + col.floor_dir.x * dist;
+    let wy = view.player.pos.y + col.floor_dir.y * dist;
+    let u = ((wx / view.block_size as f32).fract() + 1.0).fract();
+    let v = ((wy / view.block_size as f32).fract() + 1.0).fract();
+
+    let gain = (0.95 / (1.0 + dist * 0.02)).clamp(0.25, 0.9);
+    let c = match cell {
+        TRAIL_CELL => TRAIL_COLOR,
+        ' ' => view.floor_tex.sample_repeat(u, v),
+        _ => view.walls.for_cell(cell).sample_repeat(u, v),
+    };
+    Some(scale_color(c, gain))
+}
+
+/// Color del piso (altura 0) en la fila `y`.
+fn shade_floor(view: &WorldView, col: &ColumnInfo, y: i32) -> Option<Color> {
+    // misma cuenta que los topes de celda, con la superficie en 0: el piso queda pegado a las paredes
+    let row_dist = view.surface_dist(0.0, y)?;
+
+    // Más allá del borde abierto del mapa no hay piso
+    if col.kind == HitKind::OutOfBounds && row_dist > col.dist {
+        return Some(VOID_COLOR);
+    }
+
+    let wx = view.player.pos.x + col.floor_dir.x * row_dist;
+    let wy = view.player.pos.y + col.floor_dir.y * row_dist;
+
+    let u = ((wx / view.block_size as f32).fract() + 1.0).fract();
+    let v = ((wy / view.block_size as f32).fract() + 1.0).fract();
+
+    let c = view.floor_tex.sample_repeat(u, v);
+    let floor_gain = (0.95 / (1.0 + row_dist * 0.02)).clamp(0.25, 0.9);
+    Some(scale_color(c, floor_gain))
+}
+
+/// Pinta una franja de filas consecutivas (`band` empieza en la fila `y0`).
+fn paint_band(view: &WorldView, columns: &[ColumnInfo], band: &mut [Color], y0: i32, w: usize) {
+    for (row, pixels) in band.chunks_mut(w).enumerate() {
+        let y = y0 + row as i32;
+        for (px, col) in pixels.iter_mut().zip(columns) {
+            let span = col.spans.iter().find(|s| s.y0 <= y && y < s.y1);
+            let c = match span.map(|s| s.kind) {
+                // A) PARED con textura
+                Some(SpanKind::Face(face)) => Some(shade_wall(view, &face, y)),
+                // B) TOPE de una celda elevada
+                Some(SpanKind::Top { cell, height }) => shade_top(view, col, cell, height, y),
+                // C) CIELO / FONDO o D) PISO
+                None if (y as f32) < view.horizon => shade_sky(view, col, y),
+                None => shade_floor(view, col, y),
+            };
+            if let Some(c) = c { *px = c; }
+
+            // E) VIDRIO y barreras: de lejos a cerca, mezclados con el alfa de su textura
+            for g in col.depth.glass.iter().rev() {
+                if let Some(over) = g.at(y) {
+                    *px = blend(*px, over);
+                }
             }
         }
     }
 }
 
+/// Renderiza el mundo en modo 3D usando raycasting.
+/// Incluye texturizado de paredes, piso y cielo.
+/// Paredes, piso y cielo usan el mismo rayo y la misma profundidad de `settings.projection`.
+/// El horizonte sube o baja con `player.pitch` (ver `Player::horizon`).
+/// Las paredes miden lo que diga `heights`: el rayo sigue de largo tras las bajas
+/// para dibujar las más altas de atrás. Las celdas transparentes (vidrio, barreras)
+/// no detienen el rayo: se mezclan encima de lo que haya detrás con el alfa de su textura.
+/// Las columnas cuyo rayo no choca con nada muestran cielo arriba y,
+/// si el rayo salió del mapa, vacío en el piso más allá del borde.
+///
+/// Primero se lanzan los rayos de todas las columnas; después el framebuffer se
+/// divide en franjas de filas que se pintan en `settings.threads` hilos.
+/// Cada píxel depende sólo de su columna, así que el resultado es el mismo con
+/// cualquier número de hilos.
+#[allow(clippy::too_many_arguments)]
+fn render_world(
+    framebuffer: &mut Framebuffer,
+    maze: &Maze,
+    heights: &Heights,
+    doors: &Doors,
+    block_size: usize,
+    player: &Player,
+    walls: &WallTex,          
+    floor_tex: &CpuImage,
+    sky_tex: &CpuImage,
+    tron_time: f32,
+    settings: &RenderSettings,
+    depth: &mut [DepthColumn],
+) {
+    let w = framebuffer.width as i32;
+    let h = framebuffer.height as i32;
+    let view = WorldView {
+        player,
+        doors,
+        block_size,
+        walls,
+        floor_tex,
+        sky_tex,
+        hh: h as f32 * 0.5,
+        horizon: player.horizon(h),
+        eye: player.eye(),
+        dpp: 70.0,
+    };
+
+    let max_top = heights.max_top(maze);
+    let columns: Vec<ColumnInfo> = (0..w)
+        .map(|i| cast_column(&view, maze, heights, max_top, settings, tron_time, i, w, h))
+        .collect();
+
+    // Guarda qué tapa cada columna (para los sprites)
+    for (slot, col) in depth.iter_mut().zip(&columns) {
+        slot.clone_from(&col.depth);
+    }
+
+    if w == 0 || h == 0 { return; }
+    let threads = settings.threads.clamp(1, h as usize);
+    let rows_per_band = (h as usize).div_ceil(threads);
+
+    if threads == 1 {
+        paint_band(&view, &columns, framebuffer.pixels_mut(), 0, w as usize);
+        return;
+    }
+
+    thread::scope(|scope| {
+        for (y0, band) in framebuffer.row_bands_mut(rows_per_band) {
+            let (view, columns) = (&view, &columns);
+            scope.spawn(move || paint_band(view, columns, band, y0 as i32, w as usize));
+        }
+    });
+}
+
 /// Verifica si el jugador está sobre la meta 'g'.
 fn player_on_goal(player: &Player, maze: &Maze, block_size: usize) -> bool {
     let (i, j) = world_to_cell(player.pos.x, player.pos.y, block_size);
@@ -393,6 +765,32 @@ fn player_on_goal(player: &Player, maze: &Maze, block_size: usize) -> bool {
     maze[i][j] == 'g'
 }
 
+/// Pista hacia la meta: ángulo relativo a la vista del jugador y celdas que faltan.
+fn goal_hint(player: &Player, maze: &Maze, block_size: usize) -> Option<(f32, usize)> {
+    let (i, j) = world_to_cell(player.pos.x, player.pos.y, block_size);
+    let path = path_to_goal(maze, (i, j))?;
+    // apunta al centro de la siguiente celda del camino (o de la actual si ya es la meta)
+    let (ni, nj) = path.get(1).copied().unwrap_or((i, j));
+    let bs = block_size as f32;
+    let tx = (nj as f32 + 0.5) * bs - player.pos.x;
+    let ty = (ni as f32 + 0.5) * bs - player.pos.y;
+    Some((ty.atan2(tx) - player.a, path.len() - 1))
+}
+
+/// Flecha en pantalla centrada en (cx, cy); ángulo 0 = hacia adelante (arriba).
+fn draw_hint_arrow(d: &mut RaylibDrawHandle, cx: f32, cy: f32, angle: f32, color: Color) {
+    let len = 28.0;
+    let dir = Vector2::new(angle.sin(), -angle.cos());
+    let tip = Vector2::new(cx + dir.x * len, cy + dir.y * len);
+    let tail = Vector2::new(cx - dir.x * len, cy - dir.y * len);
+    d.draw_line_ex(tail, tip, 4.0, color);
+    for side in [-1.0f32, 1.0] {
+        let a = angle + PI + side * 0.5;
+        let end = Vector2::new(tip.x + a.sin() * 14.0, tip.y - a.cos() * 14.0);
+        d.draw_line_ex(tip, end, 4.0, color);
+    }
+}
+
 /// Dibuja una textura a pantalla completa (pantallas de inicio y victoria).
 fn draw_fullscreen(d: &mut RaylibDrawHandle, tex: &Texture2D, w: i32, h: i32) {
     // Dibuja la textura escalada a toda la ventana
@@ -401,39 +799,320 @@ fn draw_fullscreen(d: &mut RaylibDrawHandle, tex: &Texture2D, w: i32, h: i32) {
     d.draw_texture_pro(tex, src, dest, Vector2::new(0.0, 0.0), 0.0, Color::WHITE);
 }
 
-/// Determina si el jugador está caminando (para reproducir SFX de pasos).
-fn is_walking(win: &RaylibHandle) -> bool {
-    win.is_key_down(KeyboardKey::KEY_W) || win.is_key_down(KeyboardKey::KEY_S)
+/// Volumen y tono del motor según la rapidez del jugador (0..1).
+/// A pie se apaga al quedarse quieto; la moto siempre ronronea.
+fn engine_mix(model: MoveModel, speed_ratio: f32) -> (f32, f32) {
+    let idle = match model {
+        MoveModel::Walk => 0.0,
+        MoveModel::LightCycle => 0.2,
+    };
+    let volume = if model == MoveModel::Walk && speed_ratio < 0.01 { 0.0 } else { idle + 0.6 * speed_ratio };
+    let pitch = 0.7 + 0.9 * speed_ratio;
+    (volume, pitch)
+}
+
+/// Resolución interna del framebuffer para una ventana dada.
+fn internal_resolution(window_w: i32, window_h: i32, divisor: u32) -> (u32, u32) {
+    let div = divisor.max(1);
+    ((window_w.max(1) as u32 / div).max(1), (window_h.max(1) as u32 / div).max(1))
+}
+
+/// Texturas de un nivel: paredes (una por defecto y una opcional por tipo de celda), piso y cielo.
+struct LevelTextures {
+    walls: WallTex,
+    floor: CpuImage,
+    sky: CpuImage,
+}
+
+impl LevelTextures {
+    fn load(level: &Level) -> Result<Self, LoadError> {
+        let mut walls = WallTex::new(CpuImage::from_path(&level.wall_default)?);
+        for (ch, path) in &level.wall_textures {
+            walls.insert(*ch, CpuImage::from_path(path)?);
+        }
+        Ok(Self {
+            walls,
+            floor: CpuImage::from_path(&level.floor_texture)?,
+            sky: CpuImage::from_path(&level.sky_texture)?,
+        })
+    }
+}
+
+/// Coloca al jugador en el spawn del nivel.
+fn spawn_player(player: &mut Player, level: &Level, block_size: usize) {
+    player.pos = level.spawn_position(block_size);
+    player.a = level.facing;
+    player.model = level.movement;
+    player.speed = 0.0;
+    player.pitch = 0.0;
+    let (i, j) = level.spawn_cell(block_size);
+    player.z = level.heights.floor(i, j);
+}
+
+/// Cuadros de animación de las motos.
+fn moto_frames() -> Result<Vec<CpuImage>, LoadError> {
+    load_frames(&[
+        "assets/sprites/moto5.png",
+        "assets/sprites/moto5.png",
+        "assets/sprites/moto4.png",
+        "assets/sprites/moto4.png",
+        ])
+}
+
+/// Motos enemigas del nivel: saca del mapa los marcadores `MOTO_CELL` y pone una moto
+/// en el centro de cada uno. Patrullan; en modo moto de luz son más rápidas y dejan estela.
+fn moto_sprites(frames: &[CpuImage], maze: &mut Maze, block_size: usize, model: MoveModel) -> Vec<Sprite> {
+    let starts = take_cells(maze, |c| c == MOTO_CELL);
+    if frames.is_empty() {
+        return Vec::new();
+    }
+    let bs = block_size as f32;
+    starts
+        .iter()
+        .map(|&(_, (i, j))| {
+            let pos = Vector2::new((j as f32 + 0.5) * bs, (i as f32 + 0.5) * bs);
+            let ai = match model {
+                MoveModel::Walk => AiController::patrol_from(maze, (i, j), 3.0),
+                MoveModel::LightCycle => AiController::patrol_from(maze, (i, j), 6.0).with_trail(),
+            };
+            Sprite::new(pos, frames.to_vec(), 6.0, 1.0).with_ai(ai)
+        })
+        .collect()
+}
+
+/// Íconos de los objetos. La llave es blanca y se tiñe con el color de cada una.
+struct PickupImages {
+    key: CpuImage,
+    energy: CpuImage,
+    disc: CpuImage,
+}
+
+impl PickupImages {
+    fn load() -> Result<Self, LoadError> {
+        Ok(Self {
+            key: CpuImage::from_path("assets/sprites/pickup_key.png")?,
+            energy: CpuImage::from_path("assets/sprites/pickup_energy.png")?,
+            disc: CpuImage::from_path("assets/sprites/pickup_disc.png")?,
+        })
+    }
+
+    /// Cuadros lisos del color de cada objeto (si no se pudieron cargar los íconos).
+    fn plain() -> Self {
+        let solid = |c: Color| CpuImage { w: 1, h: 1, pixels: vec![c] };
+        Self {
+            key: solid(Color::WHITE),
+            energy: solid(PickupKind::Energy.color()),
+            disc: solid(PickupKind::Disc.color()),
+        }
+    }
+
+    /// Cuadros de animación de un objeto: brilla y se apaga.
+    fn frames(&self, kind: PickupKind) -> Vec<CpuImage> {
+        let img = match kind {
+            PickupKind::Key(_) => tinted(&self.key, kind.color()),
+            PickupKind::Energy => self.energy.clone(),
+            PickupKind::Disc => self.disc.clone(),
+        };
+        let dim = tinted(&img, Color::new(170, 170, 170, 255));
+        vec![img, dim]
+    }
+}
+
+/// Multiplica cada píxel por `tint` (respeta el alfa).
+fn tinted(img: &CpuImage, tint: Color) -> CpuImage {
+    let mul = |c: u8, t: u8| (c as u16 * t as u16 / 255) as u8;
+    let pixels = img.pixels
+        .iter()
+        .map(|c| Color::new(mul(c.r, tint.r), mul(c.g, tint.g), mul(c.b, tint.b), c.a))
+        .collect();
+    CpuImage { w: img.w, h: img.h, pixels }
+}
+
+/// Objetos del nivel como sprites en el centro de su celda.
+fn pickup_sprites(images: &PickupImages, pickups: &[(PickupKind, (usize, usize))], block_size: usize) -> Vec<Sprite> {
+    let bs = block_size as f32;
+    pickups
+        .iter()
+        .map(|&(kind, (i, j))| {
+            let pos = Vector2::new((j as f32 + 0.5) * bs, (i as f32 + 0.5) * bs);
+            Sprite::new(pos, images.frames(kind), 3.0, 0.4).with_pickup(kind)
+        })
+        .collect()
+}
+
+/// Inventario en la esquina inferior izquierda: llaves (con su color), energía y discos.
+fn draw_inventory(d: &mut RaylibDrawHandle, inventory: &Inventory, x: i32, y: i32) {
+    d.draw_text("Llaves:", x, y, 18, Color::GRAY);
+    let mut x = x + 70;
+    for &key in &inventory.keys {
+        d.draw_rectangle(x, y - 1, 18, 20, PickupKind::Key(key).color());
+        d.draw_text(&key.to_string(), x + 5, y, 18, Color::BLACK);
+        x += 24;
+    }
+    x += 16;
+    if inventory.energy_total > 0 {
+        let text = format!("Energia: {}/{}", inventory.energy, inventory.energy_total);
+        d.draw_text(&text, x, y, 18, PickupKind::Energy.color());
+        x += 150;
+    }
+    if inventory.discs_total > 0 {
+        let text = format!("Discos: {}/{}", inventory.discs, inventory.discs_total);
+        d.draw_text(&text, x, y, 18, PickupKind::Disc.color());
+    }
+}
+
+/// Marca las motos (rojo) y los objetos (su color) en el minimapa.
+fn draw_minimap_sprites(
+    framebuffer: &mut Framebuffer,
+    sprites: &[Sprite],
+    block_size: usize,
+    origin_x: usize,
+    origin_y: usize,
+    scale: usize,
+) {
+    let r = (scale / 2).max(1) as i32;
+    for s in sprites {
+        framebuffer.set_current_color(s.pickup.map_or(Color::RED, PickupKind::color));
+        let x = origin_x as i32 + (s.pos.x / block_size as f32 * scale as f32) as i32;
+        let y = origin_y as i32 + (s.pos.y / block_size as f32 * scale as f32) as i32;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if x + dx >= 0 && y + dy >= 0 {
+                    framebuffer.set_pixel((x + dx) as u32, (y + dy) as u32);
+                }
+            }
+        }
+    }
+}
+
+/// Renderiza un cuadro 3D sin abrir ventana y lo guarda en `out` (.png o .ppm).
+/// Sirve para CI o máquinas sin pantalla: todo se dibuja en el framebuffer de CPU.
+fn render_headless(level: &str, out: &str, width: u32, height: u32, threads: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let block_size = 150;
+    let level = load_level(level)?;
+    let mut player = Player::new(Vector2::new(190.0, 190.0), PI / 3.0);
+    spawn_player(&mut player, &level, block_size);
+
+    let tex = LevelTextures::load(&level)?;
+    let mut maze = level.maze.clone();
+    let pickups = take_pickups(&mut maze);
+    let mut sprites = moto_sprites(&moto_frames()?, &mut maze, block_size, level.movement);
+    sprites.extend(pickup_sprites(&PickupImages::load()?, &pickups, block_size));
+
+    let mut framebuffer = Framebuffer::new(width, height);
+    framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+    framebuffer.clear();
+    let mut depth = vec![DepthColumn::default(); width as usize];
+
+    let settings = RenderSettings::new(threads);
+    render_world(
+        &mut framebuffer, &maze, &level.heights, &Doors::from_maze(&maze), block_size, &player,
+        &tex.walls, &tex.floor, &tex.sky, 0.0,
+        &settings,
+        &mut depth,
+    );
+    render_sprites(&mut framebuffer, &player, &mut sprites, &level.heights, block_size, settings.projection, &depth);
+
+    framebuffer.render_to_file(out)
+        .map_err(|e| format!("no se pudo guardar {}: {}", out, e))?;
+    Ok(())
+}
+
+/// Valida los niveles dados e imprime sus problemas.
+/// Devuelve `true` si todos cargan y no tienen diagnósticos.
+fn check_levels(paths: &[String]) -> bool {
+    let block_size = 150;
+    let mut all_ok = true;
+    for path in paths {
+        match load_level(path) {
+            Ok(level) => {
+                let diagnostics = level.validate(block_size);
+                if diagnostics.is_empty() {
+                    println!("{}: ok", path);
+                }
+                for d in &diagnostics {
+                    println!("{}: {}", path, d);
+                }
+                all_ok &= diagnostics.is_empty();
+            }
+            Err(e) => {
+                println!("{}", e);
+                all_ok = false;
+            }
+        }
+    }
+    all_ok
 }
 
 /// Función principal del juego.
 /// Maneja el ciclo principal, estados, renderizado y entrada de usuario.
+/// Con `--render <nivel> <salida.png|ppm> [ancho alto]` renderiza un cuadro sin ventana y sale.
+/// Con `--check <nivel>...` valida los niveles y sale con código 1 si alguno tiene problemas.
+/// `--threads N` (en cualquier posición) fija los hilos del render 3D.
 fn main() {
     let window_width = 1300;
     let window_height = 900;
     let block_size = 150;
     let minimap_block_size = 150;
 
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut threads = default_render_threads();
+    if let Some(k) = args.iter().position(|a| a == "--threads") {
+        if let Some(n) = args.get(k + 1).and_then(|v| v.parse().ok()) {
+            threads = n;
+        }
+        args.drain(k..(k + 2).min(args.len()));
+    }
+
+    if args.get(1).map(String::as_str) == Some("--render") {
+        let (Some(level), Some(out)) = (args.get(2), args.get(3)) else {
+            eprintln!("Uso: --render <nivel.txt> <salida.png|ppm> [ancho alto]");
+            std::process::exit(2);
+        };
+        let width = args.get(4).and_then(|v| v.parse().ok()).unwrap_or(window_width as u32);
+        let height = args.get(5).and_then(|v| v.parse().ok()).unwrap_or(window_height as u32);
+        if let Err(e) = render_headless(level, out, width, height, threads) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("--check") {
+        if args.len() < 3 {
+            eprintln!("Uso: --check <nivel.txt>...");
+            std::process::exit(2);
+        }
+        let ok = check_levels(&args[2..]);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     let (mut window, raylib_thread) = raylib::init()
         .size(window_width, window_height)
         .title("Raycaster Project")
         .log_level(TraceLogLevel::LOG_WARNING)
+        .resizable()
         .build();
+    window.set_window_min_size(320, 240);
 
     //Musica
     let audio = RaylibAudio::init_audio_device()
         .expect("No se pudo inicializar el dispositivo de audio");
 
-    // Música de fondo
-    let music = audio.new_music("assets/music/tronMusic.ogg")
+    // Música de fondo (cada nivel puede cambiarla con @music)
+    let mut music_path = level::DEFAULT_MUSIC.to_string();
+    let mut music = audio.new_music(&music_path)
         .expect("Falta assets/music/tronMusic.ogg");
     music.set_volume(0.3);    
     music.play_stream();      
 
-    // SFX
-    let step_sfx = audio.new_sound("assets/sfx/motor.ogg")
+    // Motor: bucle continuo, volumen y tono siguen la rapidez del jugador
+    let engine = audio.new_music("assets/sfx/motor.ogg")
         .expect("Falta assets/sfx/motor.ogg");
-    step_sfx.set_volume(0.8);
+    engine.set_volume(0.0);
+    engine.play_stream();
+
+    // SFX
     let win_sfx = audio.new_sound("assets/sfx/winSound.mp3")
         .expect("Falta assets/sfx/winSound.mp3");
 
@@ -453,60 +1132,110 @@ fn main() {
             .expect("win_page.png no encontrada"),
     };
 
-    let mut walls = WallTex::new(CpuImage::from_path("assets/textures/wall_grid4.jpg"));
-
-    // Opcionales por tipo de celda
-    walls.insert('+', CpuImage::from_path("assets/textures/wall_grid8.jpg"));
-    walls.insert('|', CpuImage::from_path("assets/textures/wall_grid7.jpg"));
-    walls.insert('-', CpuImage::from_path("assets/textures/wall_grid3.jpg"));
-    // meta 'g' también tenga su propia textura:
-    walls.insert('g', CpuImage::from_path("assets/textures/wall_grid6.jpg"));
-
-    let floor_cpu = CpuImage::from_path("assets/textures/floor3.jpg");
-    let sky_cpu   = CpuImage::from_path("assets/textures/wall_grid.jpg");
-
-    
-    let mut maze = load_maze("assets/maps/level1.txt");
-    let mut player = Player {
-        pos: Vector2::new(150.0, 150.0),
-        a: PI / 3.0,
-        fov: PI / 3.0,
-    };
-
-    let mut mode_2d = false;
-    let mut state = GameState::Title;
     let levels: Vec<&str> = vec![
         "assets/maps/level1.txt",
         "assets/maps/level2.txt",
         "assets/maps/level3.txt"
     ];
+    // nombres para el menú (encabezado @name o, si no carga, la ruta) y problemas de cada mapa
+    let (mut level_names, mut level_diagnostics): (Vec<String>, Vec<Vec<String>>) = levels
+        .iter()
+        .map(|p| match load_level(p) {
+            Ok(l) => {
+                let diagnostics = l.validate(block_size).iter().map(|d| d.to_string()).collect();
+                (l.name, diagnostics)
+            }
+            Err(e) => (p.to_string(), vec![e.to_string()]),
+        })
+        .unzip();
+
+    // Última entrada del menú: laberinto aleatorio (←/→ cambian la semilla, TAB el algoritmo)
+    let mut random_config = MazeConfig::new(6, 4, std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|t| t.as_secs() % 10_000)
+        .unwrap_or(1));
+    let menu_len = levels.len() + 1;
+
+    // El nivel se carga al elegirlo en el menú; hasta entonces no hay texturas
+    let mut tex: Option<LevelTextures> = None;
+    let mut maze: Maze = Vec::new();
+    let mut heights = Heights::default();
+    let mut doors = Doors::default();
+    // error del último nivel que no se pudo cargar (se muestra en el menú)
+    let mut load_error: Option<String> = None;
+    let mut player = Player::new(Vector2::new(150.0, 150.0), PI / 3.0);
+
+    let mut mode_2d = false;
+    let mut show_hint = false; // flecha hacia la meta (H)
+
+    // Controles (archivo de asignaciones; si no existe, los de fábrica)
+    let mut bindings = Bindings::load(BINDINGS_FILE).unwrap_or_else(|e| {
+        eprintln!("Controles por defecto: {}", e);
+        Bindings::default()
+    });
+    let mut controls_selected: usize = 0;
+    let mut rebinding = false; // esperando la nueva entrada de la acción seleccionada
+    let mut controls_status: Option<String> = None;
+    // editor abierto (E en el menú); sigue vivo mientras se prueba el nivel
+    let mut editor: Option<Editor> = None;
+
+    // nivel en juego (para reintentar tras chocar) y estela de la moto del jugador
+    let mut current_level: Option<Level> = None;
+    let mut trail = TrailWriter::new();
+    let mut settings = RenderSettings::new(threads);
+    let mut state = GameState::Title;
     let mut selected_level: usize = 0;
 
     // cursor: libre en menús, capturado en juego
     window.enable_cursor();
-    let mut step_cd: f32 = 0.0;
     let mut tron_time: f32 = 0.0;
     let screen_w = framebuffer.width as usize;
-    let mut depth = vec![f32::INFINITY; screen_w];
+    let mut depth = vec![DepthColumn::default(); screen_w];
+
+    // Resolución interna = ventana / render_divisor (F3 cambia 1, 1/2, 1/3; F4 escalado entero)
+    let mut render_divisor: u32 = 1;
+
+    // motos: los cuadros se cargan una vez; las motos se crean al empezar cada nivel
+    let moto_frames = moto_frames().unwrap_or_else(|e| {
+        eprintln!("Sin sprites: {}", e);
+        Vec::new()
+    });
+    let pickup_images = PickupImages::load().unwrap_or_else(|e| {
+        eprintln!("Sin iconos de objetos: {}", e);
+        PickupImages::plain()
+    });
+    let mut sprites: Vec<Sprite> = Vec::new();
+    let mut inventory = Inventory::default();
+    let mut crash_reason = "";
+
+    // último error al presentar (se reporta una vez, no en cada cuadro)
+    let mut present_error: Option<String> = None;
 
-    let orb_frames = load_frames(&[
-        "assets/sprites/moto5.png",
-        "assets/sprites/moto5.png",
-        "assets/sprites/moto4.png",
-        "assets/sprites/moto4.png",
-        ]);
+    while !window.window_should_close() {
+        // Tamaño de ventana actual (puede cambiar al redimensionar)
+        let window_width = window.get_screen_width();
+        let window_height = window.get_screen_height();
 
-    let mut sprites = vec![
-        Sprite::new(Vector2::new(450.0, 260.0), orb_frames.clone(), 6.0, 1.0),
-        Sprite::new(Vector2::new(700.0, 400.0), orb_frames.clone(), 6.0, 1.0),
-        Sprite::new(Vector2::new(300.0, 600.0), orb_frames.clone(), 6.0, 1.0),
-    ];
+        if window.is_key_pressed(KeyboardKey::KEY_F3) {
+            render_divisor = render_divisor % 3 + 1;
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_F4) {
+            framebuffer.integer_scaling = !framebuffer.integer_scaling;
+        }
+        let (render_w, render_h) = internal_resolution(window_width, window_height, render_divisor);
+        if render_w != framebuffer.width || render_h != framebuffer.height {
+            framebuffer.resize(render_w, render_h);
+            depth.resize(render_w as usize, DepthColumn::default());
+        }
 
-    while !window.window_should_close() {
         framebuffer.clear();
+        let presented: Result<(), String>;
+        // nivel listo para jugar (desde el menú o el editor); se arranca al final del cuadro
+        let mut start_level: Option<(Level, LevelTextures)> = None;
         let dt = window.get_frame_time();
-        if step_cd > 0.0 { step_cd -= dt; }
         music.update_stream();
+        engine.update_stream();
+        if state != GameState::Playing { engine.set_volume(0.0); } // motor sólo en juego
         tron_time += dt;
 
 
@@ -514,66 +1243,181 @@ fn main() {
             GameState::Title => {
                 // Input
                 if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
-                    state = if levels.len() > 1 { GameState::LevelSelect } else { GameState::Playing };
-                    if state == GameState::Playing {
-                        window.disable_cursor();
-                    } else {
-                        window.enable_cursor();
-                    }
+                    state = GameState::LevelSelect;
+                    window.enable_cursor();
+                }
+                if window.is_key_pressed(KeyboardKey::KEY_C) {
+                    state = GameState::Controls;
+                    controls_status = None;
                 }
 
                 // Presentar con overlay de UI
-                framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
                     draw_fullscreen(d, &assets.initial, window_width, window_height); // 👈 fondo
                     d.draw_text("RAYCASTER", 500, 300, 48, Color::YELLOW);
                     d.draw_text("Presiona ENTER para empezar", 400, 440, 24, Color::WHITE);
                     d.draw_text("Presiona M para alternar 2D/3D durante el juego", 400, 470, 20, Color::GRAY);
-                    d.draw_text("Controles: W/S mover, A/D girar, Mouse mirar", 400, 500, 20, Color::GRAY);
+                    d.draw_text("Controles: W/S mover, Q/E paso lateral, A/D girar, Shift correr, Mouse mirar", 400, 500, 20, Color::GRAY);
+                    d.draw_text("P: proyeccion perspectiva/retro", 400, 530, 20, Color::GRAY);
+                    d.draw_text("H: flecha hacia la meta", 400, 560, 20, Color::GRAY);
+                    d.draw_text("C: configurar controles", 400, 590, 20, Color::GRAY);
                 });
             }
 
             GameState::LevelSelect => {
                 // Navegación
                 if window.is_key_pressed(KeyboardKey::KEY_DOWN) {
-                    selected_level = (selected_level + 1) % levels.len();
+                    selected_level = (selected_level + 1) % menu_len;
                 }
                 if window.is_key_pressed(KeyboardKey::KEY_UP) {
-                    selected_level = (selected_level + levels.len() - 1) % levels.len();
+                    selected_level = (selected_level + menu_len - 1) % menu_len;
                 }
-                if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
-                    maze = load_maze(levels[selected_level]);
-                    // reubica jugador  spawn fijo:
-                    player.pos = Vector2::new(190.0, 190.0);
-                    player.a = PI / 3.0;
-                    mode_2d = false;
-
-                    state = GameState::Playing;
-                    window.disable_cursor();
+                let random_selected = selected_level == levels.len();
+                if random_selected {
+                    if window.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+                        random_config.seed += 1;
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_LEFT) {
+                        random_config.seed = random_config.seed.saturating_sub(1);
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_TAB) {
+                        random_config.algorithm = random_config.algorithm.next();
+                    }
+                }
+                let random_name = format!(
+                    "Aleatorio: {} (semilla {})",
+                    random_config.algorithm.label(),
+                    random_config.seed,
+                );
+                if window.is_key_pressed(KeyboardKey::KEY_DOWN) || window.is_key_pressed(KeyboardKey::KEY_UP) {
+                    load_error = None;
+                }
+                let play = window.is_key_pressed(KeyboardKey::KEY_ENTER);
+                let edit = window.is_key_pressed(KeyboardKey::KEY_E);
+                if play || edit {
+                    let (level, path) = if random_selected {
+                        let level = Level::with_defaults(&random_name, generate(&random_config));
+                        (Ok(level), format!("assets/maps/random_{}.txt", random_config.seed))
+                    } else {
+                        (load_level(levels[selected_level]), levels[selected_level].to_string())
+                    };
+                    let loaded = if edit {
+                        level.map(|level| {
+                            editor = Some(Editor::new(level, &path));
+                            state = GameState::Editor;
+                        })
+                    } else {
+                        level.and_then(|level| LevelTextures::load(&level).map(|t| {
+                            editor = None;
+                            start_level = Some((level, t));
+                        }))
+                    };
+                    match loaded {
+                        Ok(()) => load_error = None,
+                        Err(e) => {
+                            eprintln!("No se pudo cargar el nivel: {}", e);
+                            load_error = Some(e.to_string());
+                        }
+                    }
                 }
                 if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
                     state = GameState::Title;
                 }
 
-                framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
                     draw_fullscreen(d, &assets.initial, window_width, window_height);
                     d.draw_text("Selecciona nivel:", 40, 40, 32, Color::YELLOW);
-                    for (idx, path) in levels.iter().enumerate() {
+                    for (idx, name) in level_names.iter().chain([&random_name]).enumerate() {
                         let y = 90 + (idx as i32)*28;
                         let color = if idx == selected_level { Color::LIME } else { Color::WHITE };
-                        d.draw_text(path, 60, y, 22, color);
+                        d.draw_text(name, 60, y, 22, color);
+                    }
+                    let help = if random_selected {
+                        "ENTER: jugar   E: editar   IZQ/DER: semilla   TAB: algoritmo   ESC: volver"
+                    } else {
+                        "ENTER: jugar   E: editar   ESC: volver"
+                    };
+                    d.draw_text(help, 40, 140 + (menu_len as i32)*28, 20, Color::GRAY);
+                    let mut y = 180 + (menu_len as i32)*28;
+                    if let Some(e) = &load_error {
+                        d.draw_text(e, 40, y, 20, Color::RED);
+                        y += 26;
+                    }
+                    // avisos de validación del nivel seleccionado (se puede jugar igual)
+                    for msg in level_diagnostics.get(selected_level).into_iter().flatten().take(6) {
+                        d.draw_text(msg, 40, y, 18, Color::ORANGE);
+                        y += 22;
                     }
-                    d.draw_text("ENTER: jugar   ESC: volver", 40, 140 + (levels.len() as i32)*28, 20, Color::GRAY);
                 });
             }
 
             GameState::Playing => {
+                let integer_scaling = framebuffer.integer_scaling;
+
                 // Input + movimiento
-                process_events(&mut player, &window, &maze, block_size);
+                let blocked = process_events(&mut player, &window, &maze, block_size, &bindings);
+                player.follow_floor(&heights, block_size);
+
+                // Objetos: se recogen al pasar encima; una llave quita la cerradura de sus puertas
+                let reach = player.radius + PICKUP_RADIUS;
+                sprites.retain(|s| match s.pickup {
+                    Some(kind) if (s.pos.x - player.pos.x).hypot(s.pos.y - player.pos.y) < reach => {
+                        inventory.add(kind);
+                        if let PickupKind::Key(key) = kind {
+                            doors.unlock(key);
+                        }
+                        false
+                    }
+                    _ => true,
+                });
+
+                // Puertas: se abren al acercarse o con "usar" y se cierran solas
+                if bindings.is_pressed(&window, Action::Use) {
+                    doors.use_from(player.pos, player.a, block_size);
+                }
+                let mut openers = vec![player.pos];
+                let mut occupants = vec![(player.pos, player.radius + player.wall_buffer)];
+                for (s, ai) in sprites.iter().filter_map(|s| Some((s, s.ai.as_ref()?))) {
+                    openers.push(s.pos);
+                    occupants.push((s.pos, ai.radius));
+                }
+                doors.update(&mut maze, dt, &openers, &occupants, block_size);
+
+                // Moto: deja estela y chocar contra una termina la ronda
+                if player.model == MoveModel::LightCycle {
+                    let bikes: Vec<(Vector2, f32)> = sprites
+                        .iter()
+                        .filter_map(|s| Some((s.pos, s.ai.as_ref()?.radius)))
+                        .collect();
+                    trail.update(&mut maze, player.pos, player.radius + player.wall_buffer, &bikes, block_size);
+                    if blocked == Blocked::Trail {
+                        crash_reason = "Te estrellaste contra una estela de luz.";
+                        state = GameState::Crashed;
+                        window.enable_cursor();
+                        mode_2d = false;
+                        continue;
+                    }
+                }
+
+                // Motor según la rapidez
+                let (engine_volume, engine_pitch) = engine_mix(player.model, player.speed_ratio());
+                engine.set_volume(engine_volume);
+                engine.set_pitch(engine_pitch);
 
-                // 👇 SFX: pasos al caminar (usa tu helper is_walking)
-                if is_walking(&window) && step_cd <= 0.0 {
-                    step_sfx.play();
-                    step_cd = 0.15; // 4 pasos por segundo aprox
+                // Motos enemigas: animación + IA; en modo moto de luz, si una te alcanza termina la ronda
+                let mut caught = false;
+                for s in sprites.iter_mut() {
+                    s.update(dt);
+                    caught |= s.think(&mut maze, &doors, &player, block_size);
+                }
+                // las que quedaron dentro de una estela se estrellaron: salen del nivel
+                sprites.retain(|s| !s.ai.as_ref().is_some_and(|ai| ai.crashed));
+                if caught && player.model == MoveModel::LightCycle {
+                    crash_reason = "Una moto enemiga te alcanzo.";
+                    state = GameState::Crashed;
+                    window.enable_cursor();
+                    mode_2d = false;
+                    continue;
                 }
 
                 // WIN check (antes de dibujar)
@@ -587,23 +1431,33 @@ fn main() {
 
 
                 // Toggle 2D/3D con M (persistente)
-                if window.is_key_pressed(KeyboardKey::KEY_M) {
+                if bindings.is_pressed(&window, Action::ToggleMap) {
                     mode_2d = !mode_2d;
                     // (opcional) cursor libre en 2D, capturado en 3D
                     if mode_2d { window.enable_cursor(); } else { window.disable_cursor(); }
                 }
 
-                if mode_2d {
-                    // Convierte window_width y window_height a usize
-                    let maze_w = maze[0].len();
-                    let maze_h = maze.len();
-                    let window_w_usize = window_width as usize;
-                    let window_h_usize = window_height as usize;
-                    let minimap_block_size_2d = (window_w_usize / maze_w).min(window_h_usize / maze_h);
+                // Proyección perspectiva/retro con P
+                if window.is_key_pressed(KeyboardKey::KEY_P) {
+                    settings.projection = settings.projection.toggled();
+                }
 
-                    // Centra el minimapa en la ventana
-                    let origin_x = (window_w_usize - maze_w * minimap_block_size_2d) / 2;
-                    let origin_y = (window_h_usize - maze_h * minimap_block_size_2d) / 2;
+                // De vuelta al editor si se está probando un nivel
+                if editor.is_some() && window.is_key_pressed(KeyboardKey::KEY_T) {
+                    state = GameState::Editor;
+                    window.enable_cursor();
+                }
+
+                // Pista hacia la meta con H
+                if window.is_key_pressed(KeyboardKey::KEY_H) {
+                    show_hint = !show_hint;
+                }
+                let hint = if show_hint { goal_hint(&player, &maze, block_size) } else { None };
+                let locked = doors.locked_near(player.pos, block_size);
+
+                if mode_2d {
+                    // Centra el minimapa en el framebuffer (resolución interna)
+                    let (origin_x, origin_y, minimap_block_size_2d) = full_map_layout(&framebuffer, &maze);
 
                     render_minimap(
                         &mut framebuffer,
@@ -614,50 +1468,295 @@ fn main() {
                         origin_y,
                         minimap_block_size_2d,
                     );
-                } else {
+                    draw_minimap_sprites(&mut framebuffer, &sprites, block_size, origin_x, origin_y, minimap_block_size_2d);
+                } else if let Some(tex) = &tex {
                     // Vista 3D + minimapa
-                    depth.fill(f32::INFINITY); // ← limpia el buffer cada frame
-
                     render_world(
-                        &mut framebuffer, &maze, block_size, &player,
-                        &walls, &floor_cpu, &sky_cpu, tron_time,
-                        &mut depth, // 👈 pásale el buffer
+                        &mut framebuffer, &maze, &heights, &doors, block_size, &player,
+                        &tex.walls, &tex.floor, &tex.sky, tron_time,
+                        &settings,
+                        &mut depth, // 👈 lo rellena cada frame
                     );
 
-                    // Actualiza y dibuja sprites
-                    for s in sprites.iter_mut() { s.update(dt); }
-                    render_sprites(&mut framebuffer, &player, &mut sprites, block_size, &depth);
+                    // Dibuja sprites (ya se movieron arriba)
+                    render_sprites(&mut framebuffer, &player, &mut sprites, &heights, block_size, settings.projection, &depth);
 
+                    // Minimapa en la esquina superior derecha, escalado con la resolución interna
+                    let mini_scale = (8 / render_divisor as usize).max(2);
+                    let mini_w = maze_width(&maze) * mini_scale;
+                    let margin = 10 / render_divisor as usize;
+                    let mini_x = (framebuffer.width as usize).saturating_sub(mini_w + margin);
                     render_minimap(
                         &mut framebuffer,
                         &maze,
                         minimap_block_size,
                         &player,
-                        1200,
-                        10,
-                        8,
+                        mini_x,
+                        margin,
+                        mini_scale,
                     );
+                    draw_minimap_sprites(&mut framebuffer, &sprites, minimap_block_size, mini_x, margin, mini_scale);
                 }
 
-                framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
                     draw_scanlines(d, window_width, window_height, 2, 40);
+                    if !mode_2d {
+                        d.draw_text(&format!("Proyeccion: {} (P)", settings.projection.label()), 10, 34, 18, Color::GRAY);
+                    }
+                    let scaling = if integer_scaling { "entero" } else { "estirado" };
+                    d.draw_text(
+                        &format!("Render: {}x{} 1/{} (F3)  escalado {} (F4)  hilos {}", render_w, render_h, render_divisor, scaling, settings.threads),
+                        10, 56, 18, Color::GRAY,
+                    );
+                    if player.model == MoveModel::LightCycle {
+                        d.draw_text(&format!("Velocidad: {:.0}%", player.speed_ratio() * 100.0), 10, 78, 18, Color::SKYBLUE);
+                    }
+                    if show_hint {
+                        let cx = window_width as f32 * 0.5;
+                        match hint {
+                            Some((angle, steps)) => {
+                                draw_hint_arrow(d, cx, 60.0, angle, Color::ORANGE);
+                                d.draw_text(&format!("Meta: {} celdas", steps), cx as i32 - 50, 100, 18, Color::ORANGE);
+                            }
+                            None => d.draw_text("Meta: sin camino", cx as i32 - 60, 100, 18, Color::RED),
+                        }
+                    }
+                    draw_inventory(d, &inventory, 10, window_height - 30);
+                    if let Some(key) = locked {
+                        let text = format!("Puerta cerrada: necesitas la llave '{}'", key);
+                        d.draw_text(&text, 10, window_height - 56, 18, PickupKind::Key(key).color());
+                    }
                 });
             }
 
             GameState::Win => {
                 // Volver a Title o repetir
                 if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
-                    state = GameState::Title;
+                    state = if editor.is_some() { GameState::Editor } else { GameState::Title };
                     window.enable_cursor();
                 }
 
-                framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
                     draw_fullscreen(d, &assets.win, window_width, window_height); // 👈 fondo
                     d.draw_text("¡FELICIDADES!", 450, 180, 50, Color::GOLD);
                     d.draw_text("¡Nivel completado!", 40, 320, 36, Color::GOLD);
                     d.draw_text("ENTER: volver al menu", 40, 370, 24, Color::WHITE);
                 });
             }
+
+            GameState::Crashed => {
+                // ENTER: reintentar el mismo nivel desde el spawn (sin estelas)
+                if window.is_key_pressed(KeyboardKey::KEY_ENTER)
+                    && let Some(level) = &current_level
+                {
+                    maze = level.maze.clone();
+                    let pickups = take_pickups(&mut maze);
+                    inventory = Inventory::for_level(&pickups);
+                    heights = level.heights.clone();
+                    doors = Doors::from_maze(&maze);
+                    spawn_player(&mut player, level, block_size);
+                    sprites = moto_sprites(&moto_frames, &mut maze, block_size, level.movement);
+                    sprites.extend(pickup_sprites(&pickup_images, &pickups, block_size));
+                    trail.reset();
+                    state = GameState::Playing;
+                    window.disable_cursor();
+                }
+                if window.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                    state = if editor.is_some() { GameState::Editor } else { GameState::Title };
+                }
+
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                    d.draw_text("¡CHOCASTE!", 40, 260, 50, Color::RED);
+                    d.draw_text(crash_reason, 40, 330, 24, Color::WHITE);
+                    d.draw_text("ENTER: reintentar   BACKSPACE: volver al menu", 40, 370, 24, Color::GRAY);
+                });
+            }
+
+            GameState::Editor => {
+                let Some(ed) = editor.as_mut() else {
+                    state = GameState::LevelSelect;
+                    continue;
+                };
+
+                let layout = full_map_layout(&framebuffer, &ed.level.maze);
+                let hovered = framebuffer
+                    .screen_to_buffer(window.get_mouse_position(), window_width, window_height)
+                    .and_then(|(x, y)| map_cell_at(&ed.level.maze, layout, x, y));
+
+                if let Some(cell) = hovered {
+                    if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+                        ed.cycle_cell(cell);
+                    }
+                    if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                        ed.set_spawn(cell);
+                    }
+                }
+
+                let ctrl = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                    || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+                if ctrl && window.is_key_pressed(KeyboardKey::KEY_Z) {
+                    ed.undo();
+                }
+                if ctrl && window.is_key_pressed(KeyboardKey::KEY_Y) {
+                    ed.redo();
+                }
+                if ctrl && window.is_key_pressed(KeyboardKey::KEY_S) {
+                    match ed.save() {
+                        Ok(()) => {
+                            // refresca el menú si el archivo es uno de sus niveles
+                            if let Some(k) = levels.iter().position(|p| *p == ed.path) {
+                                level_names[k] = ed.level.name.clone();
+                                level_diagnostics[k] = ed.level.validate(block_size).iter().map(|d| d.to_string()).collect();
+                            }
+                        }
+                        Err(e) => ed.status = Some(format!("No se pudo guardar {}: {}", ed.path, e)),
+                    }
+                }
+
+                // T: probar en 3D tal como está (sin guardar)
+                if window.is_key_pressed(KeyboardKey::KEY_T) {
+                    match LevelTextures::load(&ed.level) {
+                        Ok(t) => start_level = Some((ed.level.clone(), t)),
+                        Err(e) => ed.status = Some(e.to_string()),
+                    }
+                }
+                if window.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                    editor = None;
+                    state = GameState::LevelSelect;
+                    continue;
+                }
+
+                // mapa completo; el marcador del jugador muestra spawn y orientación
+                let spawn_marker = Player::new(ed.level.spawn_position(block_size), ed.level.facing);
+                let (origin_x, origin_y, scale) = layout;
+                render_minimap(&mut framebuffer, &ed.level.maze, block_size, &spawn_marker, origin_x, origin_y, scale);
+                if let Some(cell) = hovered {
+                    draw_cell_outline(&mut framebuffer, layout, cell, Color::WHITE);
+                }
+
+                let title = format!("Editor: {}{}", ed.path, if ed.is_dirty() { " *" } else { "" });
+                let status = ed.status.clone();
+                let problems: Vec<String> = ed.level.validate(block_size).iter().map(|d| d.to_string()).collect();
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                    d.draw_text(&title, 10, 34, 20, Color::YELLOW);
+                    d.draw_text(
+                        "Clic izq: cambiar celda   Clic der: spawn   Ctrl+Z/Y: deshacer/rehacer   Ctrl+S: guardar   T: probar / volver   BACKSPACE: menu",
+                        10, 58, 16, Color::GRAY,
+                    );
+                    let mut y = 80;
+                    if let Some(msg) = &status {
+                        d.draw_text(msg, 10, y, 18, Color::LIME);
+                        y += 22;
+                    }
+                    for msg in problems.iter().take(4) {
+                        d.draw_text(msg, 10, y, 16, Color::ORANGE);
+                        y += 20;
+                    }
+                });
+            }
+
+            GameState::Controls => {
+                let action = ACTIONS[controls_selected];
+                if rebinding {
+                    // la siguiente tecla, botón o stick queda asignada
+                    if let Some(b) = Binding::capture(&mut window) {
+                        bindings.rebind(action, b);
+                        rebinding = false;
+                    }
+                } else {
+                    if window.is_key_pressed(KeyboardKey::KEY_DOWN) {
+                        controls_selected = (controls_selected + 1) % ACTIONS.len();
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_UP) {
+                        controls_selected = (controls_selected + ACTIONS.len() - 1) % ACTIONS.len();
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                        rebinding = true;
+                        controls_status = None;
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_R) {
+                        bindings.reset(action);
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_S) {
+                        controls_status = Some(match bindings.save(BINDINGS_FILE) {
+                            Ok(()) => format!("Guardado en {}", BINDINGS_FILE),
+                            Err(e) => format!("No se pudo guardar {}: {}", BINDINGS_FILE, e),
+                        });
+                    }
+                    if window.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                        state = GameState::Title;
+                    }
+                }
+
+                let rows: Vec<(String, String)> = ACTIONS
+                    .iter()
+                    .map(|&a| (a.label().to_string(), bindings.describe(a)))
+                    .collect();
+                presented = framebuffer.present_with_ui(&mut window, &raylib_thread, |d| {
+                    draw_fullscreen(d, &assets.initial, window_width, window_height);
+                    d.draw_text("Controles:", 40, 40, 32, Color::YELLOW);
+                    for (idx, (label, inputs)) in rows.iter().enumerate() {
+                        let y = 90 + (idx as i32)*28;
+                        let color = if idx == controls_selected { Color::LIME } else { Color::WHITE };
+                        d.draw_text(label, 60, y, 22, color);
+                        let shown = if rebinding && idx == controls_selected { "presiona una tecla o boton..." } else { inputs };
+                        d.draw_text(shown, 300, y, 22, color);
+                    }
+                    let y = 110 + (rows.len() as i32)*28;
+                    d.draw_text("ENTER: reasignar   R: restaurar   S: guardar   BACKSPACE: volver", 40, y, 20, Color::GRAY);
+                    if let Some(msg) = &controls_status {
+                        d.draw_text(msg, 40, y + 30, 20, Color::LIME);
+                    }
+                });
+            }
+        }
+
+        // Arranca el nivel elegido: spawn, texturas, música y mapa
+        if let Some((level, level_tex)) = start_level {
+            for d in level.validate(block_size) {
+                eprintln!("{}: {}", level.name, d);
+            }
+            // reubica jugador en el spawn del nivel (o el spawn fijo por defecto)
+            spawn_player(&mut player, &level, block_size);
+            tex = Some(level_tex);
+
+            // cambia de pista sólo si el nivel usa otra
+            if level.music != music_path {
+                match audio.new_music(&level.music) {
+                    Ok(m) => {
+                        music.stop_stream();
+                        music = m;
+                        music.set_volume(0.3);
+                        music.play_stream();
+                        music_path = level.music.clone();
+                    }
+                    Err(e) => eprintln!("No se pudo cargar la música {}: {}", level.music, e),
+                }
+            }
+
+            maze = level.maze.clone();
+            let pickups = take_pickups(&mut maze);
+            inventory = Inventory::for_level(&pickups);
+            heights = level.heights.clone();
+            doors = Doors::from_maze(&maze);
+            sprites = moto_sprites(&moto_frames, &mut maze, block_size, level.movement);
+            sprites.extend(pickup_sprites(&pickup_images, &pickups, block_size));
+            trail.reset();
+            current_level = Some(level);
+            mode_2d = false;
+
+            state = GameState::Playing;
+            window.disable_cursor();
+        }
+
+        match presented {
+            Err(e) => {
+                if present_error.as_ref() != Some(&e) {
+                    eprintln!("Error al presentar el cuadro: {}", e);
+                }
+                present_error = Some(e);
+            }
+            Ok(()) => present_error = None,
         }
 
         thread::sleep(Duration::from_millis(16));
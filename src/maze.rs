@@ -1,18 +1,65 @@
 // maze.rs
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use raylib::prelude::Vector2;
+use std::fmt;
+
+use crate::doors::{lock_key, DOOR_CELL, OPEN_DOOR_CELL};
+use crate::error::LoadError;
+use crate::level::load_level;
+use crate::pathfind::{goal_cells, path_to_goal, unlock_reachable};
+use crate::pickup::is_pickup;
 
 pub type Maze = Vec<Vec<char>>;
 
-pub fn load_maze(filename: &str) -> Maze {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
+/// Marcador de moto enemiga: aparece en el centro de la celda.
+pub const MOTO_CELL: char = 'm';
+
+/// Celdas fijas del mapa: vacío, paredes, meta, transparentes, puerta y moto.
+/// Las puertas con cerradura y los objetos salen de `LOCKED_DOORS` y `PICKUP_CELLS`.
+pub const BASE_CELLS: &[char] = &[' ', '+', '-', '|', 'g', '#', ':', '%', DOOR_CELL, MOTO_CELL];
+
+/// ¿Es una celda que entiende el juego sin que el nivel la declare?
+pub fn is_known_cell(c: char) -> bool {
+    BASE_CELLS.contains(&c) || lock_key(c).is_some() || is_pickup(c)
+}
+
+/// Celdas que los rayos atraviesan: se dibujan encima de lo que haya detrás.
+/// El `bool` dice si además bloquean el paso (`is_wall`).
+pub const TRANSPARENT_CELLS: &[(char, bool)] = &[
+    ('#', true),  // vidrio
+    (':', true),  // barrera de energía
+    ('%', false), // rejilla holográfica: se ve pero se cruza
+];
+
+/// ¿La celda marca dónde aparece algo (un objeto o una moto)? Es piso vacío
+/// para el movimiento y los rayos; el juego la vacía al empezar el nivel.
+pub fn is_marker(c: char) -> bool {
+    is_pickup(c) || c == MOTO_CELL
+}
+
+/// Saca del mapa las celdas que cumplen `pred` (quedan vacías)
+/// y devuelve qué carácter había en cada una (fila, columna).
+pub fn take_cells(maze: &mut Maze, pred: impl Fn(char) -> bool) -> Vec<(char, (usize, usize))> {
+    let mut out = Vec::new();
+    for (i, row) in maze.iter_mut().enumerate() {
+        for (j, c) in row.iter_mut().enumerate() {
+            if pred(*c) {
+                out.push((*c, (i, j)));
+                *c = ' ';
+            }
+        }
+    }
+    out
+}
 
-    reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect()
+/// ¿Los rayos ven a través de esta celda?
+pub fn is_transparent(c: char) -> bool {
+    TRANSPARENT_CELLS.iter().any(|&(t, _)| t == c)
+}
+
+/// Carga sólo la cuadrícula de un archivo de nivel (el encabezado `@` se ignora).
+pub fn load_maze(filename: &str) -> Result<Maze, LoadError> {
+    load_level(filename).map(|level| level.maze)
 }
 
 pub fn world_to_cell(x: f32, y: f32, block_size: usize) -> (usize, usize) {
@@ -21,9 +68,134 @@ pub fn world_to_cell(x: f32, y: f32, block_size: usize) -> (usize, usize) {
     (i, j)
 }
 
+/// Devuelve el carácter de la celda (i, j), o `None` si cae fuera del mapa
+/// o más allá del final de una fila corta.
+pub fn cell_at(maze: &Maze, i: usize, j: usize) -> Option<char> {
+    maze.get(i).and_then(|row| row.get(j)).copied()
+}
+
+/// Ancho del mapa: la fila más larga (los mapas pueden tener filas disparejas).
+pub fn maze_width(maze: &Maze) -> usize {
+    maze.iter().map(|row| row.len()).max().unwrap_or(0)
+}
+
 pub fn is_wall(maze: &Maze, i: usize, j: usize) -> bool {
     if i >= maze.len() { return true; }
     if j >= maze[i].len() { return true; }
     let c = maze[i][j];
-    c != ' ' && c != 'g' // 'g' lo reservamos como meta (no pared)
-}
\ No newline at end of file
+    let passable = TRANSPARENT_CELLS.iter().any(|&(t, blocks)| t == c && !blocks);
+    // 'g' lo reservamos como meta (no pared); una puerta es pared hasta que se abre del todo
+    // y los marcadores (objetos, motos) están sobre piso vacío
+    c != ' ' && c != 'g' && c != OPEN_DOOR_CELL && !passable && !is_marker(c)
+}
+/// ¿Un círculo (centro en mundo, radio) toca alguna pared?
+pub fn circle_hits_wall(maze: &Maze, center: Vector2, radius: f32, block_size: usize) -> bool {
+    if center.x - radius < 0.0 || center.y - radius < 0.0 {
+        return true; // fuera del mapa por arriba/izquierda
+    }
+    circle_hits(center, radius, block_size, |i, j| is_wall(maze, i, j))
+}
+
+/// ¿Un círculo toca alguna celda con el carácter `ch`?
+pub fn circle_hits_cell(maze: &Maze, center: Vector2, radius: f32, block_size: usize, ch: char) -> bool {
+    circle_hits(center, radius, block_size, |i, j| cell_at(maze, i, j) == Some(ch))
+}
+
+/// Revisa las celdas que cubre la caja del círculo y devuelve si alguna `solid` lo toca.
+fn circle_hits(center: Vector2, radius: f32, block_size: usize, solid: impl Fn(usize, usize) -> bool) -> bool {
+    let (i0, j0) = world_to_cell(center.x - radius, center.y - radius, block_size);
+    let (i1, j1) = world_to_cell(center.x + radius, center.y + radius, block_size);
+    (i0..=i1).any(|i| (j0..=j1).any(|j| solid(i, j) && circle_overlaps_cell(center, radius, (i, j), block_size)))
+}
+
+/// ¿El círculo se mete en la celda (i, j)? Usa el punto de la celda más cercano al centro.
+pub fn circle_overlaps_cell(center: Vector2, radius: f32, (i, j): (usize, usize), block_size: usize) -> bool {
+    let bs = block_size as f32;
+    let nx = center.x.clamp(j as f32 * bs, (j + 1) as f32 * bs);
+    let ny = center.y.clamp(i as f32 * bs, (i + 1) as f32 * bs);
+    let (dx, dy) = (center.x - nx, center.y - ny);
+    dx * dx + dy * dy < radius * radius
+}
+
+/// Problema encontrado por `validate`. Las celdas son (fila, columna).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// Carácter que no es una celda conocida ni fue declarado por el nivel.
+    UnknownChar { cell: (usize, usize), ch: char },
+    /// Fila con un ancho distinto al de la fila más larga.
+    RaggedRow { row: usize, len: usize, expected: usize },
+    /// Celda transitable en el borde: el jugador (y los rayos) pueden salir del mapa.
+    BorderHole { cell: (usize, usize) },
+    /// El spawn cae en una pared o fuera del mapa.
+    SpawnInWall { cell: (usize, usize) },
+    /// El mapa no tiene meta 'g'.
+    NoGoal,
+    /// Ninguna meta se alcanza caminando desde el spawn (con las llaves que se puedan recoger).
+    UnreachableGoal { cell: (usize, usize) },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::UnknownChar { cell: (i, j), ch } => {
+                write!(f, "fila {}, columna {}: caracter desconocido {:?}", i + 1, j + 1, ch)
+            }
+            Diagnostic::RaggedRow { row, len, expected } => {
+                write!(f, "fila {}: mide {} y la mas larga {}", row + 1, len, expected)
+            }
+            Diagnostic::BorderHole { cell: (i, j) } => {
+                write!(f, "fila {}, columna {}: hueco en el borde exterior", i + 1, j + 1)
+            }
+            Diagnostic::SpawnInWall { cell: (i, j) } => {
+                write!(f, "fila {}, columna {}: el spawn esta dentro de una pared", i + 1, j + 1)
+            }
+            Diagnostic::NoGoal => write!(f, "no hay meta 'g'"),
+            Diagnostic::UnreachableGoal { cell: (i, j) } => {
+                write!(f, "fila {}, columna {}: la meta no se alcanza desde el spawn", i + 1, j + 1)
+            }
+        }
+    }
+}
+
+/// Revisa un mapa antes de jugarlo. `spawn` es la celda inicial y `declared`
+/// los caracteres extra que el nivel acepta (p. ej. los de `@wall`).
+/// Devuelve la lista de problemas; vacía si el mapa está bien.
+pub fn validate(maze: &Maze, spawn: (usize, usize), declared: &[char]) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let width = maze_width(maze);
+
+    for (i, row) in maze.iter().enumerate() {
+        for (j, &ch) in row.iter().enumerate() {
+            if !is_known_cell(ch) && !declared.contains(&ch) {
+                out.push(Diagnostic::UnknownChar { cell: (i, j), ch });
+            }
+        }
+        if row.len() != width {
+            out.push(Diagnostic::RaggedRow { row: i, len: row.len(), expected: width });
+        }
+    }
+
+    // Borde: primera y última fila, primera y última columna de cada fila
+    let last = maze.len().saturating_sub(1);
+    for (i, row) in maze.iter().enumerate() {
+        for (j, _) in row.iter().enumerate() {
+            let on_border = i == 0 || i == last || j == 0 || j + 1 == row.len();
+            if on_border && !is_wall(maze, i, j) {
+                out.push(Diagnostic::BorderHole { cell: (i, j) });
+            }
+        }
+    }
+
+    let goals = goal_cells(maze);
+
+    if is_wall(maze, spawn.0, spawn.1) {
+        out.push(Diagnostic::SpawnInWall { cell: spawn });
+    }
+
+    if goals.is_empty() {
+        out.push(Diagnostic::NoGoal);
+    } else if !is_wall(maze, spawn.0, spawn.1) && path_to_goal(&unlock_reachable(maze, spawn), spawn).is_none() {
+        out.extend(goals.iter().map(|&cell| Diagnostic::UnreachableGoal { cell }));
+    }
+    out
+}
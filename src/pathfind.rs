@@ -0,0 +1,148 @@
+// pathfind.rs
+
+//! Caminos más cortos sobre las celdas del `Maze` (A*, 4 direcciones).
+//! Una celda es transitable si `is_wall` dice que no es pared (la meta 'g' lo es).
+//! Las puertas también cuentan como transitables aunque estén cerradas: se abren al llegar.
+//! Las de cerradura no: son pared mientras sigan como `K`/`J` en el mapa (al recoger su llave
+//! `Doors` las vuelve puertas normales).
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::doors::{is_door, lock_key, DOOR_CELL};
+use crate::maze::{cell_at, is_wall, Maze};
+use crate::pickup::PickupKind;
+
+/// ¿Se puede pasar por (i, j)? Como `is_wall`, pero con las puertas sin cerradura abiertas.
+fn passable(maze: &Maze, i: usize, j: usize) -> bool {
+    !is_wall(maze, i, j) || cell_at(maze, i, j).is_some_and(|c| is_door(c) && lock_key(c).is_none())
+}
+
+/// Celdas vecinas transitables de (i, j).
+fn neighbors(maze: &Maze, (i, j): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let up = i.checked_sub(1).map(|i| (i, j));
+    let left = j.checked_sub(1).map(|j| (i, j));
+    [Some((i + 1, j)), Some((i, j + 1)), up, left]
+        .into_iter()
+        .flatten()
+        .filter(|&(i, j)| passable(maze, i, j))
+}
+
+fn manhattan(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// A* desde `from` hasta la primera celda de `goals` que se alcance.
+/// Devuelve el camino incluyendo ambos extremos.
+fn astar(maze: &Maze, from: (usize, usize), goals: &[(usize, usize)]) -> Option<Vec<(usize, usize)>> {
+    if goals.is_empty() || !passable(maze, from.0, from.1) {
+        return None;
+    }
+    let width = maze.iter().map(|row| row.len()).max().unwrap_or(0);
+    let index = |(i, j): (usize, usize)| i * width + j;
+    let heuristic = |c: (usize, usize)| goals.iter().map(|&g| manhattan(c, g)).min().unwrap_or(0);
+
+    let mut cost = vec![usize::MAX; maze.len() * width];
+    let mut came_from: Vec<Option<(usize, usize)>> = vec![None; maze.len() * width];
+    let mut open = BinaryHeap::new();
+    cost[index(from)] = 0;
+    open.push(Reverse((heuristic(from), 0, from)));
+
+    while let Some(Reverse((_, g, cell))) = open.pop() {
+        if g > cost[index(cell)] { continue; } // entrada vieja
+        if goals.contains(&cell) {
+            let mut path = vec![cell];
+            let mut cur = cell;
+            while let Some(prev) = came_from[index(cur)] {
+                path.push(prev);
+                cur = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in neighbors(maze, cell) {
+            let ng = g + 1;
+            if ng < cost[index(next)] {
+                cost[index(next)] = ng;
+                came_from[index(next)] = Some(cell);
+                open.push(Reverse((ng + heuristic(next), ng, next)));
+            }
+        }
+    }
+    None
+}
+
+/// Camino más corto entre dos celdas (fila, columna), incluyendo ambas.
+/// `None` si alguna es pared o no hay conexión.
+pub fn find_path(maze: &Maze, from: (usize, usize), to: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+    if !passable(maze, to.0, to.1) {
+        return None;
+    }
+    astar(maze, from, &[to])
+}
+
+/// Celdas de meta 'g' del mapa.
+pub fn goal_cells(maze: &Maze) -> Vec<(usize, usize)> {
+    maze.iter()
+        .enumerate()
+        .flat_map(|(i, row)| row.iter().enumerate().filter(|(_, c)| **c == 'g').map(move |(j, _)| (i, j)))
+        .collect()
+}
+
+/// Camino más corto desde `from` hasta la meta más cercana.
+pub fn path_to_goal(maze: &Maze, from: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+    astar(maze, from, &goal_cells(maze))
+}
+
+/// Pasos (celdas) que faltan para llegar a la meta más cercana.
+pub fn distance_to_goal(maze: &Maze, from: (usize, usize)) -> Option<usize> {
+    path_to_goal(maze, from).map(|path| path.len() - 1)
+}
+
+/// Distancia en pasos desde `from` a cada celda alcanzable (BFS), en orden de cercanía.
+pub fn distances_from(maze: &Maze, from: (usize, usize)) -> Vec<((usize, usize), usize)> {
+    if !passable(maze, from.0, from.1) {
+        return Vec::new();
+    }
+    let width = maze.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut seen = vec![vec![false; width]; maze.len()];
+    seen[from.0][from.1] = true;
+    let mut out = vec![(from, 0)];
+    let mut k = 0;
+    while k < out.len() {
+        let (cell, d) = out[k];
+        for (i, j) in neighbors(maze, cell) {
+            if !seen[i][j] {
+                seen[i][j] = true;
+                out.push(((i, j), d + 1));
+            }
+        }
+        k += 1;
+    }
+    out
+}
+
+/// Copia del mapa con abiertas las puertas de cerradura que se pueden abrir desde `from`:
+/// recoge las llaves alcanzables, abre sus puertas y repite hasta que no aparezcan llaves nuevas.
+pub fn unlock_reachable(maze: &Maze, from: (usize, usize)) -> Maze {
+    let mut maze = maze.clone();
+    let mut keys: Vec<char> = Vec::new();
+    loop {
+        let found: Vec<char> = distances_from(&maze, from)
+            .into_iter()
+            .filter_map(|((i, j), _)| match PickupKind::from_cell(maze[i][j]) {
+                Some(PickupKind::Key(k)) if !keys.contains(&k) => Some(k),
+                _ => None,
+            })
+            .collect();
+        if found.is_empty() {
+            return maze;
+        }
+        for c in maze.iter_mut().flatten() {
+            if lock_key(*c).is_some_and(|k| found.contains(&k)) {
+                *c = DOOR_CELL;
+            }
+        }
+        keys.extend(found);
+    }
+}
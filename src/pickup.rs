@@ -0,0 +1,91 @@
+// pickup.rs
+
+//! Objetos que se recogen: llaves, celdas de energía y discos de datos.
+//! Van en el mapa con su carácter; al empezar el nivel se sacan de la cuadrícula
+//! (la celda queda vacía) y se vuelven sprites que se recogen al pasar encima.
+//! Cada llave abre las puertas con cerradura que le corresponden (ver `LOCKED_DOORS`).
+
+use raylib::prelude::Color;
+
+use crate::maze::{take_cells, Maze};
+
+/// Distancia extra (sobre el radio del jugador) a la que se recoge un objeto.
+pub const PICKUP_RADIUS: f32 = 30.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickupKind {
+    Key(char), // el carácter de la llave ('k', 'j')
+    Energy,
+    Disc,
+}
+
+/// Caracteres de objeto en el mapa.
+pub const PICKUP_CELLS: &[char] = &['k', 'j', 'e', 'o'];
+
+impl PickupKind {
+    pub fn from_cell(c: char) -> Option<Self> {
+        match c {
+            'k' | 'j' => Some(PickupKind::Key(c)),
+            'e' => Some(PickupKind::Energy),
+            'o' => Some(PickupKind::Disc),
+            _ => None,
+        }
+    }
+
+    /// Color del objeto (HUD, minimapa y tinte de las puertas que abre).
+    pub fn color(self) -> Color {
+        match self {
+            PickupKind::Key('j') => Color::new(255, 80, 200, 255),
+            PickupKind::Key(_) => Color::new(80, 255, 120, 255),
+            PickupKind::Energy => Color::new(255, 220, 40, 255),
+            PickupKind::Disc => Color::new(0, 220, 255, 255),
+        }
+    }
+}
+
+/// ¿La celda es un objeto? (para `is_wall` cuenta como piso)
+pub fn is_pickup(c: char) -> bool {
+    PICKUP_CELLS.contains(&c)
+}
+
+/// Saca los objetos del mapa y devuelve cuáles eran y en qué celda (fila, columna).
+pub fn take_pickups(maze: &mut Maze) -> Vec<(PickupKind, (usize, usize))> {
+    take_cells(maze, is_pickup)
+        .into_iter()
+        .filter_map(|(c, cell)| Some((PickupKind::from_cell(c)?, cell)))
+        .collect()
+}
+
+/// Lo que lleva el jugador en el nivel actual.
+#[derive(Clone, Debug, Default)]
+pub struct Inventory {
+    pub keys: Vec<char>,
+    pub energy: u32,
+    pub discs: u32,
+    pub energy_total: u32,
+    pub discs_total: u32,
+}
+
+impl Inventory {
+    /// Inventario vacío que sabe cuántos objetos hay en el nivel.
+    pub fn for_level(pickups: &[(PickupKind, (usize, usize))]) -> Self {
+        let count = |k: PickupKind| pickups.iter().filter(|(p, _)| *p == k).count() as u32;
+        Self {
+            energy_total: count(PickupKind::Energy),
+            discs_total: count(PickupKind::Disc),
+            ..Self::default()
+        }
+    }
+
+    pub fn add(&mut self, kind: PickupKind) {
+        match kind {
+            PickupKind::Key(k) => {
+                if !self.keys.contains(&k) {
+                    self.keys.push(k);
+                }
+            }
+            PickupKind::Energy => self.energy += 1,
+            PickupKind::Disc => self.discs += 1,
+        }
+    }
+}
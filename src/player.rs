@@ -2,53 +2,236 @@
 
 use raylib::prelude::*;
 use std::f32::consts::PI;
-use crate::maze::{Maze, is_wall, world_to_cell};
+use crate::input::{Action, Bindings};
+use crate::heights::Heights;
+use crate::maze::{Maze, circle_hits_cell, circle_hits_wall, world_to_cell};
+use crate::trail::TRAIL_CELL;
+
+/// Radio del jugador en unidades de mundo (bloques de 150).
+pub const PLAYER_RADIUS: f32 = 20.0;
+/// Margen extra contra las paredes para que la cámara no se meta en ellas.
+pub const WALL_BUFFER: f32 = 12.0;
+
+/// Velocidades por defecto (por cuadro).
+pub const MOVE_SPEED: f32 = 4.0;
+pub const TURN_SPEED: f32 = PI / 60.0;
+pub const SPRINT_FACTOR: f32 = 1.8;
+pub const MOUSE_SENSITIVITY: f32 = 0.003;
+/// Altura de los ojos sobre el piso (en alturas de pared).
+pub const EYE_HEIGHT: f32 = 0.5;
+/// Inclinación vertical máxima de la cámara (radianes, hacia arriba o abajo).
+pub const MAX_PITCH: f32 = PI / 8.0;
+
+/// Cómo responde el jugador a los controles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveModel {
+    /// Cámara a pie: velocidad constante, paso lateral, gira en el lugar.
+    Walk,
+    /// Moto de luz: inercia, aceleración, freno y giro más abierto a mayor velocidad.
+    LightCycle,
+}
+
+impl MoveModel {
+    /// Nombre en el encabezado del nivel (`@movement`).
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveModel::Walk => "walk",
+            MoveModel::LightCycle => "cycle",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "walk" => Some(MoveModel::Walk),
+            "cycle" => Some(MoveModel::LightCycle),
+            _ => None,
+        }
+    }
+}
+
+/// Parámetros de la moto (por cuadro, en unidades de mundo).
+#[derive(Clone, Copy, Debug)]
+pub struct CycleTuning {
+    pub acceleration: f32,
+    pub braking: f32,
+    pub drag: f32,          // frenado sin acelerar
+    pub max_speed: f32,
+    pub turn_at_max: f32,   // fracción de `turn_speed` que queda a velocidad máxima
+}
+
+impl Default for CycleTuning {
+    fn default() -> Self {
+        Self { acceleration: 0.12, braking: 0.35, drag: 0.03, max_speed: 9.0, turn_at_max: 0.35 }
+    }
+}
 
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
+    pub pitch: f32, // mirada vertical (+ = arriba), en ±MAX_PITCH
+    pub z: f32,     // altura del piso bajo el jugador (sigue a `Heights::floor`)
     pub fov: f32, // field of view
+    pub radius: f32,      // cuerpo para colisiones
+    pub wall_buffer: f32, // distancia mínima extra a las paredes
+    pub move_speed: f32,  // unidades de mundo por cuadro
+    pub turn_speed: f32,  // radianes por cuadro
+    pub sprint_factor: f32,
+    pub mouse_sensitivity: f32,
+    pub model: MoveModel,
+    pub speed: f32,       // rapidez actual (unidades por cuadro)
+    pub cycle: CycleTuning,
 }
 
-pub fn process_events(player: &mut Player, rl: &RaylibHandle, maze: &Maze, block_size: usize) {
-    const MOVE_SPEED: f32 = 4.0;
-    const ROTATION_SPEED: f32 = PI / 60.0;
+impl Player {
+    pub fn new(pos: Vector2, a: f32) -> Self {
+        Self {
+            pos,
+            a,
+            pitch: 0.0,
+            z: 0.0,
+            fov: PI / 3.0,
+            radius: PLAYER_RADIUS,
+            wall_buffer: WALL_BUFFER,
+            move_speed: MOVE_SPEED,
+            turn_speed: TURN_SPEED,
+            sprint_factor: SPRINT_FACTOR,
+            mouse_sensitivity: MOUSE_SENSITIVITY,
+            model: MoveModel::Walk,
+            speed: 0.0,
+            cycle: CycleTuning::default(),
+        }
+    }
+
+    /// Rapidez máxima del modelo actual (con turbo).
+    pub fn top_speed(&self) -> f32 {
+        match self.model {
+            MoveModel::Walk => self.move_speed * self.sprint_factor,
+            MoveModel::LightCycle => self.cycle.max_speed * self.sprint_factor,
+        }
+    }
+
+    /// Rapidez actual en 0..1 respecto a `top_speed` (sonido del motor, HUD).
+    pub fn speed_ratio(&self) -> f32 {
+        (self.speed / self.top_speed().max(0.0001)).clamp(0.0, 1.0)
+    }
 
-    // --- Rotación con A/D
-    if rl.is_key_down(KeyboardKey::KEY_A) {
-        player.a -= ROTATION_SPEED;
+    /// Fila de la pantalla (alto `h`) donde queda el horizonte según `pitch`.
+    /// Mirar arriba lo baja; paredes, piso, cielo y sprites usan esta misma fila.
+    /// Usa la escala vertical de las paredes (`hh` por unidad de pendiente), no la horizontal:
+    /// así el horizonte se mueve igual que ellas y con `MAX_PITCH` no pasa de media pantalla.
+    pub fn horizon(&self, h: i32) -> f32 {
+        let hh = h as f32 * 0.5;
+        hh + self.pitch.tan() * hh
     }
-    if rl.is_key_down(KeyboardKey::KEY_D) {
-        player.a += ROTATION_SPEED;
+
+    /// Altura de los ojos (piso + `EYE_HEIGHT`).
+    pub fn eye(&self) -> f32 {
+        self.z + EYE_HEIGHT
     }
 
-    // --- Movimiento hacia adelante/atrás con W/S
-    let mut forward = 0.0;
-    if rl.is_key_down(KeyboardKey::KEY_W) { forward += 1.0; }
-    if rl.is_key_down(KeyboardKey::KEY_S) { forward -= 1.0; }
+    /// Sube o baja suavemente hasta el piso de la celda donde está.
+    pub fn follow_floor(&mut self, heights: &Heights, block_size: usize) {
+        let (i, j) = world_to_cell(self.pos.x, self.pos.y, block_size);
+        self.z += (heights.floor(i, j) - self.z) * 0.3;
+    }
 
-    let dir = Vector2::new(player.a.cos(), player.a.sin());
-    let step = Vector2::new(dir.x * (MOVE_SPEED * forward), dir.y * (MOVE_SPEED * forward));
+    /// ¿El jugador cabría en `pos` sin tocar paredes (con el margen)?
+    pub fn fits_at(&self, maze: &Maze, pos: Vector2, block_size: usize) -> bool {
+        !circle_hits_wall(maze, pos, self.radius + self.wall_buffer, block_size)
+    }
 
-    let next_x = player.pos.x + step.x;
-    let next_y = player.pos.y + step.y;
+    /// Contra qué choca en `pos` (donde no cabe): estela si alguna de las celdas tocadas lo es.
+    fn blocker_at(&self, maze: &Maze, pos: Vector2, block_size: usize) -> Blocked {
+        if circle_hits_cell(maze, pos, self.radius + self.wall_buffer, block_size, TRAIL_CELL) {
+            Blocked::Trail
+        } else {
+            Blocked::Wall
+        }
+    }
+}
+
+/// Qué frenó el movimiento en `process_events` (de menos a más grave).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Blocked {
+    No,
+    Wall,
+    Trail,
+}
 
-    // --- Colisión
-    let (ci, cj) = world_to_cell(next_x, next_y, block_size);
-    if !is_wall(maze, ci, cj) {
-        player.pos.x = next_x;
-        player.pos.y = next_y;
+/// Aplica controles y colisiones. Devuelve qué frenó el movimiento (si algo lo frenó).
+pub fn process_events(player: &mut Player, rl: &RaylibHandle, maze: &Maze, block_size: usize, bindings: &Bindings) -> Blocked {
+    let axis = |neg: Action, pos: Action| bindings.strength(rl, pos) - bindings.strength(rl, neg);
+    let turn = axis(Action::TurnLeft, Action::TurnRight);
+    let sprint = bindings.is_down(rl, Action::Sprint);
+
+    let step = match player.model {
+        MoveModel::Walk => {
+            // --- Rotación (A/D por defecto)
+            player.a += turn * player.turn_speed;
+
+            // --- Avance y paso lateral (W/S, Q/E por defecto); correr con Shift
+            let forward = axis(Action::Back, Action::Forward);
+            let strafe = axis(Action::StrafeLeft, Action::StrafeRight);
+            let mut speed = player.move_speed;
+            if sprint { speed *= player.sprint_factor; }
+
+            let dir = Vector2::new(player.a.cos(), player.a.sin());
+            let right = Vector2::new(-dir.y, dir.x);
+            let mut step = Vector2::new(dir.x * forward + right.x * strafe, dir.y * forward + right.y * strafe);
+            let len = (step.x * step.x + step.y * step.y).sqrt();
+            if len > 1.0 { step = Vector2::new(step.x / len, step.y / len); } // en diagonal no se va más rápido
+            Vector2::new(step.x * speed, step.y * speed)
+        }
+        MoveModel::LightCycle => {
+            // --- Acelerador (avanzar), freno (retroceder), turbo (correr); sin reversa
+            let c = player.cycle;
+            let max = if sprint { c.max_speed * player.sprint_factor } else { c.max_speed };
+            let throttle = bindings.strength(rl, Action::Forward);
+            let brake = bindings.strength(rl, Action::Back);
+            player.speed += throttle * c.acceleration - brake * c.braking;
+            if throttle == 0.0 { player.speed -= c.drag; }
+            if player.speed > max { player.speed = (player.speed - c.braking).max(max); }
+            player.speed = player.speed.max(0.0);
+
+            // --- Giro: más cerrado despacio, más abierto rápido
+            let t = (player.speed / c.max_speed).clamp(0.0, 1.0);
+            player.a += turn * player.turn_speed * (1.0 - t * (1.0 - c.turn_at_max));
+
+            Vector2::new(player.a.cos() * player.speed, player.a.sin() * player.speed)
+        }
+    };
+
+    // --- Colisión por eje: si un eje choca, el otro sigue (se desliza por la pared)
+    let start = player.pos;
+    let mut blocked = Blocked::No;
+    let next_x = Vector2::new(player.pos.x + step.x, player.pos.y);
+    if player.fits_at(maze, next_x, block_size) {
+        player.pos.x = next_x.x;
+    } else if step.x != 0.0 {
+        blocked = blocked.max(player.blocker_at(maze, next_x, block_size));
+    }
+    let next_y = Vector2::new(player.pos.x, player.pos.y + step.y);
+    if player.fits_at(maze, next_y, block_size) {
+        player.pos.y = next_y.y;
+    } else if step.y != 0.0 {
+        blocked = blocked.max(player.blocker_at(maze, next_y, block_size));
     }
 
-    // Sensibilidad del mouse 
-    const MOUSE_SENSITIVITY: f32 = 0.003;
+    // rapidez real: a pie es lo que se movió; la moto pierde lo que la pared le quitó
+    let moved = ((player.pos.x - start.x).powi(2) + (player.pos.y - start.y).powi(2)).sqrt();
+    match player.model {
+        MoveModel::Walk => player.speed = moved,
+        MoveModel::LightCycle => player.speed = player.speed.min(moved),
+    }
 
-    // Rotación por mouse (solo horizontal)
+    // Rotación por mouse: x gira, y inclina la mirada (limitada)
     let md = rl.get_mouse_delta();    // Vector2: x,y
-    player.a += md.x * MOUSE_SENSITIVITY;
+    player.a += md.x * player.mouse_sensitivity;
+    player.pitch = (player.pitch - md.y * player.mouse_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
 
     // normalizar ángulo para que no crezca indefinidamente
     if player.a > std::f32::consts::PI { player.a -= 2.0*std::f32::consts::PI; }
     if player.a < -std::f32::consts::PI { player.a += 2.0*std::f32::consts::PI; }
 
+    blocked
 }
@@ -0,0 +1,104 @@
+// projection.rs
+
+use raylib::prelude::*;
+
+use crate::player::Player;
+
+/// Cómo se proyectan los rayos de cada columna sobre la pantalla.
+/// `Perspective` usa un plano de cámara y distancia perpendicular (sin ojo de pez);
+/// `Retro` reparte los ángulos linealmente y usa la distancia euclidiana,
+/// como el render original (paredes curvadas en los bordes).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,
+    Retro,
+}
+
+/// Rayo de una columna de pantalla.
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnRay {
+    pub angle: f32,     // ángulo del rayo para el raycast
+    pub floor_dir: Vector2, // punto de piso/cielo = pos + floor_dir * row_dist
+    scale: f32,         // factor de distancia euclidiana -> profundidad
+}
+
+impl ColumnRay {
+    /// Convierte la distancia euclidiana del raycast en la profundidad que usa la proyección.
+    #[inline]
+    pub fn depth(&self, distance: f32) -> f32 {
+        distance * self.scale
+    }
+}
+
+impl Projection {
+    /// Alterna entre los dos modos.
+    pub fn toggled(self) -> Self {
+        match self {
+            Projection::Perspective => Projection::Retro,
+            Projection::Retro => Projection::Perspective,
+        }
+    }
+
+    /// Nombre corto para la UI.
+    pub fn label(self) -> &'static str {
+        match self {
+            Projection::Perspective => "perspectiva",
+            Projection::Retro => "retro",
+        }
+    }
+
+    /// Rayo para la columna `x` de una pantalla de ancho `w`.
+    pub fn column_ray(self, player: &Player, x: i32, w: i32) -> ColumnRay {
+        match self {
+            Projection::Perspective => {
+                let dir = Vector2::new(player.a.cos(), player.a.sin());
+                let half = (player.fov * 0.5).tan();
+                let plane = Vector2::new(-dir.y * half, dir.x * half);
+                let camera_x = 2.0 * (x as f32 + 0.5) / w as f32 - 1.0;
+                let raw = Vector2::new(dir.x + plane.x * camera_x, dir.y + plane.y * camera_x);
+                let len = (raw.x * raw.x + raw.y * raw.y).sqrt();
+                ColumnRay {
+                    angle: raw.y.atan2(raw.x),
+                    floor_dir: raw,
+                    scale: 1.0 / len,
+                }
+            }
+            Projection::Retro => {
+                let t = x as f32 / w as f32;
+                let a = player.a - (player.fov * 0.5) + (player.fov * t);
+                ColumnRay {
+                    angle: a,
+                    floor_dir: Vector2::new(a.cos(), a.sin()),
+                    scale: 1.0,
+                }
+            }
+        }
+    }
+
+    /// Proyecta un punto del mundo: devuelve (x en pantalla, profundidad)
+    /// o `None` si queda detrás o muy fuera del campo de visión.
+    pub fn project_point(self, player: &Player, pos: Vector2, w: i32) -> Option<(f32, f32)> {
+        let dx = pos.x - player.pos.x;
+        let dy = pos.y - player.pos.y;
+        match self {
+            Projection::Perspective => {
+                let (sin, cos) = player.a.sin_cos();
+                let forward = dx * cos + dy * sin;
+                let side = -dx * sin + dy * cos;
+                if forward < 0.0005 { return None; }
+                let half = (player.fov * 0.5).tan();
+                let screen_x = (w as f32 * 0.5) * (1.0 + side / (forward * half));
+                Some((screen_x, forward))
+            }
+            Projection::Retro => {
+                let dist = (dx * dx + dy * dy).sqrt().max(0.0005);
+                let mut angle = dy.atan2(dx) - player.a;
+                while angle > std::f32::consts::PI { angle -= 2.0 * std::f32::consts::PI; }
+                while angle < -std::f32::consts::PI { angle += 2.0 * std::f32::consts::PI; }
+                if angle.abs() > player.fov { return None; }
+                let screen_x = ((angle / player.fov) + 0.5) * w as f32;
+                Some((screen_x, dist))
+            }
+        }
+    }
+}
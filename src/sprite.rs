@@ -1,9 +1,15 @@
 use raylib::prelude::*;
-use std::f32::consts::PI;
 
+use crate::ai::AiController;
+use crate::doors::Doors;
+use crate::error::LoadError;
 use crate::framebuffer::Framebuffer;
-use crate::player::Player;
-use crate::CpuImage;
+use crate::heights::Heights;
+use crate::maze::{world_to_cell, Maze};
+use crate::pickup::PickupKind;
+use crate::player::{Player, EYE_HEIGHT};
+use crate::projection::Projection;
+use crate::{blend, CpuImage};
 
 pub struct Sprite {
     pub pos: Vector2,        // posición en mundo (misma escala que player.pos)
@@ -11,17 +17,39 @@ pub struct Sprite {
     pub fps: f32,            // cuadros por segundo
     pub t: f32,              // tiempo acumulado
     pub scale: f32,          // factor extra (1.0 = tamaño base)
+    pub ai: Option<AiController>, // None = decorado quieto
+    pub pickup: Option<PickupKind>, // se recoge al pasar encima
 }
 
 impl Sprite {
     pub fn new(pos: Vector2, frames: Vec<CpuImage>, fps: f32, scale: f32) -> Self {
-        Self { pos, frames, fps, t: 0.0, scale }
+        Self { pos, frames, fps, t: 0.0, scale, ai: None, pickup: None }
+    }
+
+    /// Le da un controlador de IA al sprite.
+    pub fn with_ai(mut self, ai: AiController) -> Self {
+        self.ai = Some(ai);
+        self
+    }
+
+    /// Vuelve al sprite un objeto que se recoge.
+    pub fn with_pickup(mut self, kind: PickupKind) -> Self {
+        self.pickup = Some(kind);
+        self
     }
 
     pub fn update(&mut self, dt: f32) {
         self.t += dt;
     }
 
+    /// Un cuadro de IA (si tiene). Devuelve `true` si alcanzó al jugador.
+    pub fn think(&mut self, maze: &mut Maze, doors: &Doors, player: &Player, block_size: usize) -> bool {
+        match self.ai.as_mut() {
+            Some(ai) => ai.update(&mut self.pos, maze, doors, player, block_size),
+            None => false,
+        }
+    }
+
     #[inline]
     fn current_frame(&self) -> &CpuImage {
         let n = self.frames.len().max(1);
@@ -31,22 +59,69 @@ impl Sprite {
 }
 
 /// Carga múltiples imágenes como frames de animación
-pub fn load_frames(paths: &[&str]) -> Vec<CpuImage> {
+pub fn load_frames(paths: &[&str]) -> Result<Vec<CpuImage>, LoadError> {
     paths.iter().map(|p| CpuImage::from_path(p)).collect()
 }
 
+/// Cara transparente de una columna, ya sombreada: un color (con alfa) por fila desde `y0`.
+#[derive(Clone, Debug)]
+pub struct GlassLayer {
+    pub dist: f32,
+    pub y0: i32,
+    pub colors: Vec<Color>,
+}
+
+impl GlassLayer {
+    /// Color del vidrio en la fila `y`, si la cubre.
+    pub fn at(&self, y: i32) -> Option<Color> {
+        usize::try_from(y - self.y0).ok().and_then(|k| self.colors.get(k)).copied()
+    }
+}
+
+/// Lo que hay delante en una columna de pantalla, de cerca a lejos.
+/// `occluders` son pares (profundidad, primera fila tapada): lo que esté más lejos que una
+/// entrada queda oculto de esa fila hacia abajo. `glass` se mezcla encima de lo que está detrás.
+#[derive(Clone, Debug, Default)]
+pub struct DepthColumn {
+    pub occluders: Vec<(f32, i32)>,
+    pub glass: Vec<GlassLayer>,
+}
+
+impl DepthColumn {
+    /// ¿Un punto a profundidad `dist` en la fila `y` queda detrás de algo de la columna?
+    fn occluded(&self, dist: f32, y: i32) -> bool {
+        self.occluders.iter().take_while(|(d, _)| *d < dist).any(|&(_, top)| y >= top)
+    }
+
+    /// Mezcla sobre `color` (a profundidad `dist`) el vidrio que tiene delante, de lejos a cerca.
+    fn behind_glass(&self, color: Color, dist: f32, y: i32) -> Color {
+        self.glass
+            .iter()
+            .rev()
+            .filter(|g| g.dist < dist)
+            .filter_map(|g| g.at(y))
+            .fold(color, blend)
+    }
+}
+
 /// Dibuja sprites con prueba de profundidad por columna.
-/// `depth[i]` debe contener la distancia del muro más cercano para esa columna (producida por el raycaster).
+/// `depth[i]` debe contener lo que tapa esa columna (producido por el raycaster),
+/// medido con la misma `projection` que se usa aquí. El vidrio que queda delante
+/// de un sprite se vuelve a mezclar encima de él. Cada sprite se para sobre el piso
+/// de su celda (`heights`).
 pub fn render_sprites(
     framebuffer: &mut Framebuffer,
     player: &Player,
     sprites: &mut [Sprite],
-    _block_size: usize,
-    depth: &[f32],
+    heights: &Heights,
+    block_size: usize,
+    projection: Projection,
+    depth: &[DepthColumn],
 ) {
     let w = framebuffer.width as i32;
     let h = framebuffer.height as i32;
     let hh = h as f32 * 0.5;
+    let horizon = player.horizon(h); // mismo horizonte que `render_world`
     let dpp = 70.0;
 
     // Ordena de lejos a cerca 
@@ -59,32 +134,24 @@ pub fn render_sprites(
     for spr in sprites.iter_mut() {
         let frame = spr.current_frame();
 
-        // Vector al sprite
-        let dx = spr.pos.x - player.pos.x;
-        let dy = spr.pos.y - player.pos.y;
-        let dist = (dx*dx + dy*dy).sqrt().max(0.0005);
-
-        // Ángulo relativo a la mirada del jugador
-        let mut angle = dy.atan2(dx) - player.a;
-        while angle >  PI { angle -= 2.0*PI; }
-        while angle < -PI { angle += 2.0*PI; }
-
-        // Si está muy fuera del FOV
-        if angle.abs() > player.fov { continue; }
-
-        // Proyección horizontal (x en pantalla)
-        let screen_x = ((angle / player.fov) + 0.5) * w as f32;
+        // Proyección horizontal (x en pantalla) y profundidad; None si está fuera del FOV
+        let Some((screen_x, dist)) = projection.project_point(player, spr.pos, w) else { continue; };
+        let dist = dist.max(0.0005);
 
         // Altura (y anchura) proyectada del sprite (billboard cuadrado)
-        let base_h = (hh / dist) * dpp * spr.scale;
+        let px_per_unit = (hh / dist) * dpp;
+        let base_h = px_per_unit * spr.scale;
+        // centro a la altura de los ojos sobre el piso de su celda
+        let (i, j) = world_to_cell(spr.pos.x, spr.pos.y, block_size);
+        let center = horizon + (player.eye() - heights.floor(i, j) - EYE_HEIGHT) * px_per_unit;
         let sprite_h = base_h as i32;
         let sprite_w = sprite_h; // cuadrado
 
         // Rectángulo en pantalla
         let left   = (screen_x as i32) - sprite_w / 2;
         let right  = left + sprite_w;
-        let top    = (hh - base_h * 0.5) as i32;
-        let bottom = (hh + base_h * 0.5) as i32;
+        let top    = (center - base_h * 0.5) as i32;
+        let bottom = (center + base_h * 0.5) as i32;
 
         // Clipping
         let cl_left   = left.max(0);
@@ -95,7 +162,7 @@ pub fn render_sprites(
 
         // Dibujo columnar con prueba de profundidad
         for sx in cl_left..cl_right {
-            let col_idx = sx as usize;
+            let Some(column) = depth.get(sx as usize) else { continue; };
             let u = (sx - left) as f32 / (right - left).max(1) as f32;
 
             for sy in cl_top..cl_bottom {
@@ -106,13 +173,10 @@ pub fn render_sprites(
                 let col = frame.sample_repeat(u, v);
                 if col.a < 10 { continue; } // transparencia
 
-                // Profundidad del muro para esta columna
-                let wall_dist = depth.get(col_idx).copied().unwrap_or(f32::INFINITY);
-
-                // Si el sprite está detrás del muro, no se dibuja
-                if dist >= wall_dist { continue; }
+                // Si el sprite está detrás de una pared (o del tope de una), no se dibuja
+                if column.occluded(dist, sy) { continue; }
 
-                framebuffer.set_current_color(col);
+                framebuffer.set_current_color(column.behind_glass(col, dist, sy));
                 framebuffer.set_pixel(sx as u32, sy as u32);
             }
         }
@@ -0,0 +1,56 @@
+// trail.rs
+
+//! Estelas de las motos de luz. Se escriben en el `Maze` como celdas `TRAIL_CELL`,
+//! así que `is_wall`, `cast_ray` y el pathfinding ya las tratan como paredes.
+
+use raylib::prelude::Vector2;
+
+use crate::maze::{cell_at, circle_overlaps_cell, world_to_cell, Maze};
+
+/// Carácter de una celda de estela.
+pub const TRAIL_CELL: char = '~';
+
+/// Deja estela en las celdas que un vehículo va abandonando.
+/// Una celda se vuelve sólida cuando el vehículo (su círculo completo) ya salió de ella,
+/// para no quedar atrapado dentro de su propia estela.
+#[derive(Clone, Debug, Default)]
+pub struct TrailWriter {
+    last: Option<(usize, usize)>,
+    pending: Vec<(usize, usize)>,
+}
+
+impl TrailWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Olvida el recorrido (nueva ronda).
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.pending.clear();
+    }
+
+    /// Avanza la estela con la posición actual del vehículo (centro y radio en mundo).
+    /// Sólo se escribe sobre celdas vacías: la meta y las paredes no cambian.
+    /// Una celda tampoco se escribe mientras algún círculo de `others` (otros vehículos) la toque.
+    pub fn update(&mut self, maze: &mut Maze, pos: Vector2, radius: f32, others: &[(Vector2, f32)], block_size: usize) {
+        let cur = world_to_cell(pos.x, pos.y, block_size);
+        if let Some(prev) = self.last
+            && prev != cur
+            && !self.pending.contains(&prev)
+        {
+            self.pending.push(prev);
+        }
+        self.last = Some(cur);
+
+        self.pending.retain(|&(i, j)| {
+            if (i, j) == cur { return false; } // volvió a entrar: se vuelve a dejar al salir
+            if circle_overlaps_cell(pos, radius, (i, j), block_size) { return true; }
+            if others.iter().any(|&(p, r)| circle_overlaps_cell(p, r, (i, j), block_size)) { return true; }
+            if cell_at(maze, i, j) == Some(' ') {
+                maze[i][j] = TRAIL_CELL;
+            }
+            false
+        });
+    }
+}